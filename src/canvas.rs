@@ -1,16 +1,36 @@
 //! A painting surface.
 
-use crate::{gpu::Shader, paint::Paint, Angle, Element, Method, P2, V2};
+use crate::{
+    gpu::{BlendMode, Geometry, Shader},
+    paint::Paint,
+    raster::{Cap, FillRule, Join, RasterOptions, StrokeStyle},
+    Angle, Element, Method, TransformStack, P2, V2,
+};
 use lyon_path::Builder;
 use palette::{Alpha, IntoColor, LinSrgb, LinSrgba};
 
+/// A disk to paint with `Canvas::disks()`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Disk {
+    pub center: P2,
+    pub radius: f32,
+    pub color: LinSrgba,
+}
+
 /// A painting surface.
 pub struct Canvas {
     path: Builder,
     shader: Shader,
+    blend_mode: BlendMode,
     color: LinSrgba,
-    stroke_width: f32,
+    stroke_style: StrokeStyle,
+    vertex_data: [f32; 4],
+    raster_options: RasterOptions,
     scale: f32,
+    layer: usize,
+    z_order: i32,
+    opacity: f32,
+    transforms: TransformStack,
     elements: Vec<Element>,
 }
 
@@ -19,9 +39,16 @@ impl Canvas {
         Self {
             path: Builder::new(),
             shader: default_shader,
+            blend_mode: BlendMode::default(),
             color: Alpha::<LinSrgb, _>::new(1., 1., 1., 1.),
             scale,
-            stroke_width: 1.,
+            layer: 0,
+            z_order: 0,
+            opacity: 1.,
+            stroke_style: StrokeStyle::new(1.),
+            vertex_data: [0.; 4],
+            raster_options: RasterOptions::default(),
+            transforms: TransformStack::default(),
             elements: vec![],
         }
     }
@@ -44,33 +71,64 @@ impl Canvas {
         };
     }
 
+    /// Saves the current transform so it can be restored with `pop_transform()`.
+    pub fn push_transform(&mut self) {
+        self.transforms.push();
+    }
+
+    /// Restores the transform saved by the last `push_transform()`.
+    pub fn pop_transform(&mut self) {
+        self.transforms.pop();
+    }
+
+    /// Translates the coordinate system of subsequent path points.
+    pub fn translate(&mut self, translation: V2) {
+        self.transforms.translate(translation);
+    }
+
+    /// Rotates the coordinate system of subsequent path points about its origin.
+    pub fn rotate(&mut self, theta: Angle) {
+        self.transforms.rotate(theta);
+    }
+
+    /// Scales the coordinate system of subsequent path points about its origin.
+    ///
+    /// This does not change the width of strokes.
+    pub fn scale(&mut self, factor: f32) {
+        self.transforms.scale(factor);
+    }
+
     /// Stats a new path at the given point.
     pub fn move_to(&mut self, dest: P2) {
         self.path = Builder::new();
-        self.path.move_to(dest * self.scale);
+        self.path.move_to(self.project(dest));
     }
 
     /// Adds a line to the current path which ends at the given point.
     pub fn line_to(&mut self, dest: P2) {
-        self.path.line_to(dest * self.scale);
+        self.path.line_to(self.project(dest));
     }
 
     /// Adds a quadratic bezier curve to the current path with the given control and end points.
     pub fn quadratic_to(&mut self, ctrl: P2, end: P2) {
         self.path
-            .quadratic_bezier_to(ctrl * self.scale, end * self.scale);
+            .quadratic_bezier_to(self.project(ctrl), self.project(end));
     }
 
     /// Adds a cubic bezier curve to the current path with the given control and end points.
     pub fn cubic_to(&mut self, ctrl0: P2, ctrl1: P2, end: P2) {
         self.path
-            .cubic_bezier_to(ctrl0 * self.scale, ctrl1 * self.scale, end * self.scale);
+            .cubic_bezier_to(self.project(ctrl0), self.project(ctrl1), self.project(end));
     }
 
     /// Adds an arc segment to the path.
     pub fn arc(&mut self, center: P2, radii: V2, sweep: Angle, phase: Angle) {
-        self.path
-            .arc(center * self.scale, radii * self.scale, sweep, phase);
+        self.path.arc(
+            self.project(center),
+            radii * self.transforms.scale_factor() * self.scale,
+            sweep,
+            phase + self.transforms.rotation(),
+        );
     }
 
     /// Closes the current path.
@@ -80,7 +138,30 @@ impl Canvas {
 
     /// Sets the width of lines drawn with the `stroke()`.
     pub fn set_stroke_width(&mut self, stroke_width: f32) {
-        self.stroke_width = stroke_width * self.scale;
+        self.stroke_style.width = stroke_width * self.scale;
+    }
+
+    /// Sets the shape of the ends of lines drawn with `stroke()`.
+    pub fn set_stroke_cap(&mut self, cap: Cap) {
+        self.stroke_style.cap = cap;
+    }
+
+    /// Sets the shape of the corners of lines drawn with `stroke()`.
+    pub fn set_stroke_join(&mut self, join: Join) {
+        self.stroke_style.join = join;
+    }
+
+    /// Sets how far past a corner a miter join may reach, as a multiple of the stroke width,
+    /// before the corner is beveled instead.
+    pub fn set_miter_limit(&mut self, miter_limit: f32) {
+        self.stroke_style.miter_limit = miter_limit;
+    }
+
+    /// Sets the alternating lengths of dashes and gaps of lines drawn with `stroke()`, and how
+    /// far into the pattern each line starts. An empty pattern draws solid lines.
+    pub fn set_stroke_dashes(&mut self, dashes: impl IntoIterator<Item = f32>, offset: f32) {
+        self.stroke_style.dashes = dashes.into_iter().map(|d| d * self.scale).collect();
+        self.stroke_style.dash_offset = offset * self.scale;
     }
 
     /// Paints the current path by filling the region inside the path.
@@ -88,9 +169,130 @@ impl Canvas {
         self.push_element(Method::Fill);
     }
 
+    /// Paints the current path by filling the region inside the path with a linear gradient.
+    ///
+    /// The gradient runs along `direction` in the bounds of the path normalized to the unit
+    /// square, through `stops` of positions along it and their colors. See
+    /// `Method::GradientFill`.
+    pub fn fill_gradient(
+        &mut self,
+        stops: impl IntoIterator<Item = (f32, LinSrgba)>,
+        direction: V2,
+    ) {
+        self.push_element(Method::GradientFill {
+            stops: stops.into_iter().collect(),
+            direction,
+        });
+    }
+
     /// Paints the current path by stroking the path.
     pub fn stroke(&mut self) {
-        self.push_element(Method::Stroke(self.stroke_width));
+        self.push_element(Method::Stroke(self.stroke_style.clone()));
+    }
+
+    /// Paints the current path by stroking the path, fading from the current color at its start to
+    /// the given color and alpha at its end. A path of several subpaths fades once across them all.
+    pub fn stroke_gradient(&mut self, end: impl IntoColor, alpha: f32) {
+        self.push_element(Method::GradientStroke {
+            style: self.stroke_style.clone(),
+            end: Alpha {
+                color: end.into_rgb(),
+                alpha,
+            },
+        });
+    }
+
+    /// Paints the current path by filling it with the current color and outlining it with a stroke
+    /// of the given color and alpha, as one element.
+    pub fn fill_and_stroke(&mut self, stroke: impl IntoColor, alpha: f32) {
+        self.push_element(Method::FillAndStroke {
+            style: self.stroke_style.clone(),
+            stroke: Alpha {
+                color: stroke.into_rgb(),
+                alpha,
+            },
+        });
+    }
+
+    /// Paints the current path by filling the region inside the path with parallel lines of the
+    /// current stroke width, `gap` apart and rotated by `angle`.
+    pub fn hachure(&mut self, angle: Angle, gap: f32) {
+        self.push_element(Method::Hachure {
+            angle: angle + self.transforms.rotation(),
+            gap: gap * self.transforms.scale_factor() * self.scale,
+            width: self.stroke_style.width,
+        });
+    }
+
+    /// Paints disks, each with its own color, in one draw call without tessellating them.
+    ///
+    /// This is much faster than filling circle paths for sketches with many dots, and gives each
+    /// disk a smooth antialiased edge. Disks are drawn with their own shader rather than the
+    /// current one, and drivers limit how large they may be (often 64 pixels across or more), so
+    /// large circles should still be painted as paths.
+    pub fn disks(&mut self, disks: impl IntoIterator<Item = Disk>) {
+        let radius_scale = self.transforms.scale_factor() * self.scale;
+        let disks = disks
+            .into_iter()
+            .map(|disk| Disk {
+                center: self.project(disk.center),
+                radius: disk.radius * radius_scale,
+                ..disk
+            })
+            .collect();
+        self.elements.push(Element {
+            geometry: Geometry::Disks(disks),
+            shader: self.shader.clone(),
+            blend_mode: self.blend_mode,
+            layer: self.layer,
+            z_order: self.z_order,
+            opacity: self.opacity,
+        });
+    }
+
+    /// Sets the data given to every vertex of subsequently painted paths, for use by custom shaders.
+    ///
+    /// Fragment shaders can read it as `in vec4 v_data;`. It defaults to zeros.
+    pub fn set_vertex_data(&mut self, vertex_data: [f32; 4]) {
+        self.vertex_data = vertex_data;
+    }
+
+    /// Sets the maximum distance, in output pixels, between subsequently painted curves and the
+    /// line segments approximating them. The default is 0.05.
+    pub fn set_tolerance(&mut self, tolerance: f32) {
+        self.raster_options.tolerance = tolerance;
+    }
+
+    /// Sets which regions of subsequently filled paths count as inside them. The default is
+    /// `FillRule::EvenOdd`.
+    pub fn set_fill_rule(&mut self, fill_rule: FillRule) {
+        self.raster_options.fill_rule = fill_rule;
+    }
+
+    /// Sets the layer of subsequently painted elements. The default layer is 0.
+    ///
+    /// Layers do not change the order elements are drawn in; they let runs render only some of
+    /// the painting. See `Options::layers`, and `set_z_order()` for ordering.
+    pub fn set_layer(&mut self, layer: usize) {
+        self.layer = layer;
+    }
+
+    /// Sets the z order of subsequently painted elements. The default is 0.
+    ///
+    /// Elements with a lower z order are drawn first, under those with a higher one. Elements
+    /// with the same z order are drawn in the order they were painted.
+    pub fn set_z_order(&mut self, z_order: i32) {
+        self.z_order = z_order;
+    }
+
+    /// Sets the opacity of subsequently painted elements, which multiplies the alpha of all of
+    /// their colors. The default is 1.
+    ///
+    /// This fades a whole group of elements at once, whatever colors they use. Custom shaders
+    /// see it in the alpha of `v_color`, which they should multiply into their output as the
+    /// built-in shaders do.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity;
     }
 
     /// Sets the current shader used to shade rastered paths.
@@ -102,15 +304,34 @@ impl Canvas {
         self.shader = shader;
     }
 
+    /// Sets how subsequently painted elements blend with what is painted under them.
+    ///
+    /// Like changing shaders, changing blend modes requires a new draw call to the GPU.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    fn project(&self, p: P2) -> P2 {
+        self.transforms.apply(p) * self.scale
+    }
+
     fn push_element(&mut self, raster_method: Method) {
         let mut path = Builder::new();
         std::mem::swap(&mut self.path, &mut path);
 
         self.elements.push(Element {
-            path,
-            color: self.color,
+            geometry: Geometry::Path {
+                path,
+                color: self.color,
+                raster_method,
+                raster_options: self.raster_options,
+                vertex_data: self.vertex_data,
+            },
             shader: self.shader.clone(),
-            raster_method,
+            blend_mode: self.blend_mode,
+            layer: self.layer,
+            z_order: self.z_order,
+            opacity: self.opacity,
         });
     }
 }
@@ -119,7 +340,9 @@ impl IntoIterator for Canvas {
     type Item = Element;
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
-    fn into_iter(self) -> Self::IntoIter {
+    /// Returns the canvas's elements in the order they are drawn.
+    fn into_iter(mut self) -> Self::IntoIter {
+        self.elements.sort_by_key(|element| element.z_order);
         self.elements.into_iter()
     }
 }
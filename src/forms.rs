@@ -1,7 +1,17 @@
 //! Forms.
 
+mod circle_packing;
 mod ellipse;
+mod growth;
+mod lsystem;
 mod ngon;
+mod poisson_disk;
 mod polygon;
+mod polyline;
+mod space_filling;
+mod vector_field;
 
-pub use self::{ellipse::*, ngon::*, polygon::*};
+pub use self::{
+    circle_packing::*, ellipse::*, growth::*, lsystem::*, ngon::*, poisson_disk::*, polygon::*,
+    polyline::*, space_filling::*, vector_field::*,
+};
@@ -0,0 +1,160 @@
+//! Circle packing.
+
+use crate::{Bounds, Ellipse, P2};
+use rand::Rng;
+
+/// Greedily packs non-overlapping circles with radii in `[min_radius, max_radius]` into the bounds.
+///
+/// Each of the `attempts` draws a random radius and center, and keeps the circle only if it lies
+/// inside the bounds and overlaps no circle kept so far. More attempts pack more densely. If
+/// `min_radius` is not below `max_radius`, every circle has `min_radius`. Radii which are not
+/// positive and finite pack no circles.
+pub fn pack_circles(
+    bounds: Bounds,
+    min_radius: f32,
+    max_radius: f32,
+    attempts: usize,
+    rng: &mut (impl Rng + ?Sized),
+) -> Vec<Ellipse> {
+    let valid = |r: f32| r > 0. && r.is_finite();
+    if !valid(min_radius) || !valid(max_radius) {
+        return vec![];
+    }
+    let largest = min_radius.max(max_radius);
+    let mut grid = Grid::new(bounds, largest * 2.);
+    let mut circles: Vec<Ellipse> = vec![];
+    for _ in 0..attempts {
+        let radius = if max_radius > min_radius {
+            rng.gen_range(min_radius, max_radius)
+        } else {
+            min_radius
+        };
+        let (min_x, max_x) = (bounds.min_x() + radius, bounds.max_x() - radius);
+        let (min_y, max_y) = (bounds.min_y() + radius, bounds.max_y() - radius);
+        if min_x >= max_x || min_y >= max_y {
+            continue;
+        }
+
+        let center = P2::new(rng.gen_range(min_x, max_x), rng.gen_range(min_y, max_y));
+        let overlaps = grid.neighbors(center).any(|i| {
+            let other = &circles[i];
+            (other.center - center).length() < other.radii.x + radius
+        });
+        if !overlaps {
+            grid.insert(center, circles.len());
+            circles.push(Ellipse::circle(center, radius));
+        }
+    }
+
+    circles
+}
+
+/// A uniform grid of buckets over the bounds, holding indices of circles by their center.
+///
+/// Cells are as wide as the largest circle, so any circle overlapping a query circle has its
+/// center in the query's cell or one of the eight around it.
+struct Grid {
+    bounds: Bounds,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    fn new(bounds: Bounds, cell_size: f32) -> Self {
+        let columns = (bounds.size.width / cell_size).ceil().max(1.) as usize;
+        let rows = (bounds.size.height / cell_size).ceil().max(1.) as usize;
+        Self {
+            bounds,
+            cell_size,
+            columns,
+            rows,
+            cells: vec![vec![]; columns * rows],
+        }
+    }
+
+    fn cell(&self, p: P2) -> (usize, usize) {
+        let column = ((p.x - self.bounds.min_x()) / self.cell_size) as usize;
+        let row = ((p.y - self.bounds.min_y()) / self.cell_size) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    fn insert(&mut self, p: P2, index: usize) {
+        let (column, row) = self.cell(p);
+        self.cells[row * self.columns + column].push(index);
+    }
+
+    fn neighbors<'a>(&'a self, p: P2) -> impl Iterator<Item = usize> + 'a {
+        let (column, row) = self.cell(p);
+        let columns = column.saturating_sub(1)..(column + 2).min(self.columns);
+        let rows = row.saturating_sub(1)..(row + 2).min(self.rows);
+        rows.flat_map(move |row| {
+            columns
+                .clone()
+                .flat_map(move |column| self.cells[row * self.columns + column].iter().copied())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::S2;
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn circles_are_packed_apart() {
+        let bounds = Bounds::new(P2::new(10., 10.), S2::new(200., 100.));
+        let circles = pack_circles(bounds, 2., 10., 2000, &mut StdRng::seed_from_u64(0));
+
+        assert!(circles.len() > 50);
+        for c in &circles {
+            let r = c.radii.x;
+            assert!((2. ..=10.).contains(&r));
+            assert!(c.center.x - r >= bounds.min_x() && c.center.x + r <= bounds.max_x());
+            assert!(c.center.y - r >= bounds.min_y() && c.center.y + r <= bounds.max_y());
+        }
+        for (a, b) in circles.iter().tuple_combinations() {
+            assert!((a.center - b.center).length() >= a.radii.x + b.radii.x);
+        }
+    }
+
+    #[test]
+    fn circles_pack_with_fixed_radius() {
+        let bounds = Bounds::new(P2::new(0., 0.), S2::new(100., 100.));
+        let circles = pack_circles(bounds, 10., 1., 2000, &mut StdRng::seed_from_u64(0));
+
+        assert!(!circles.is_empty());
+        assert!(circles.iter().all(|c| c.radii.x == 10.));
+        for (a, b) in circles.iter().tuple_combinations() {
+            assert!((a.center - b.center).length() >= a.radii.x + b.radii.x);
+        }
+    }
+
+    #[test]
+    fn invalid_radii_pack_nothing() {
+        let bounds = Bounds::new(P2::new(0., 0.), S2::new(100., 100.));
+        let mut rng = StdRng::seed_from_u64(0);
+        for (min, max) in &[
+            (0., 0.),
+            (0., 5.),
+            (-1., 5.),
+            (1., f32::NAN),
+            (1., f32::INFINITY),
+        ] {
+            assert!(pack_circles(bounds, *min, *max, 100, &mut rng).is_empty());
+        }
+    }
+
+    #[test]
+    fn circles_pack_from_any_rng() {
+        let bounds = Bounds::new(P2::new(0., 0.), S2::new(50., 50.));
+        let rng: &mut dyn rand::RngCore = &mut StdRng::seed_from_u64(0);
+        assert_eq!(
+            pack_circles(bounds, 2., 5., 100, rng),
+            pack_circles(bounds, 2., 5., 100, &mut StdRng::seed_from_u64(0))
+        );
+    }
+}
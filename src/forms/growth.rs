@@ -0,0 +1,281 @@
+//! Differential growth.
+
+use crate::{Canvas, FlatIterPath, Paint, P2, V2};
+use itertools::iproduct;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// A differential growth simulation over a line of nodes.
+///
+/// Each step, nodes are pushed away from any node within the repulsion radius and pulled toward
+/// the midpoint of their neighbors along the line, and segments stretched past the split length
+/// get a new node at their midpoint. Over many steps the line grows into folded, coral-like
+/// curves which the repulsion keeps from crossing itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DifferentialGrowth {
+    /// Nodes closer than this push each other apart. A radius which is not positive and finite
+    /// turns repulsion off.
+    pub repulsion_radius: f32,
+    /// The fraction of the repulsion radius a node moves when another node is right on top of it.
+    pub repulsion: f32,
+    /// The fraction of the way to the midpoint of its neighbors a node moves each step.
+    pub attraction: f32,
+    /// Segments longer than this are split in two. A length which is not positive and finite
+    /// turns splitting off.
+    pub split_length: f32,
+    /// The largest random nudge given to each node each step, which breaks up symmetry. A jitter
+    /// which is not positive and finite turns it off.
+    pub jitter: f32,
+    nodes: Vec<P2>,
+    closed: bool,
+}
+
+impl DifferentialGrowth {
+    /// Starts a simulation from the given nodes, closing them into a loop if `closed`.
+    ///
+    /// The start should have its nodes spaced no further apart than the split length.
+    pub fn new(nodes: impl IntoIterator<Item = P2>, closed: bool) -> Self {
+        Self {
+            repulsion_radius: 10.,
+            repulsion: 0.2,
+            attraction: 0.1,
+            split_length: 5.,
+            jitter: 0.01,
+            nodes: nodes.into_iter().collect(),
+            closed,
+        }
+    }
+
+    pub fn with_repulsion(self, repulsion_radius: f32, repulsion: f32) -> Self {
+        Self {
+            repulsion_radius,
+            repulsion,
+            ..self
+        }
+    }
+
+    pub fn with_attraction(self, attraction: f32) -> Self {
+        Self { attraction, ..self }
+    }
+
+    pub fn with_split_length(self, split_length: f32) -> Self {
+        Self {
+            split_length,
+            ..self
+        }
+    }
+
+    pub fn with_jitter(self, jitter: f32) -> Self {
+        Self { jitter, ..self }
+    }
+
+    /// Returns an iterator over the simulation's nodes in order along the line.
+    pub fn nodes<'a>(&'a self) -> impl DoubleEndedIterator<Item = P2> + Clone + 'a {
+        self.nodes.iter().copied()
+    }
+
+    /// Advances the simulation by one step. Randomness is only drawn for jitter.
+    pub fn step(&mut self, rng: &mut (impl Rng + ?Sized)) {
+        let grid = if valid(self.repulsion_radius) {
+            Some(self.grid())
+        } else {
+            None
+        };
+        let forces = (0..self.nodes.len())
+            .map(|i| {
+                let repulsion = grid
+                    .as_ref()
+                    .map_or(V2::zero(), |grid| self.repulsion_at(grid, i));
+                repulsion + self.attraction_at(i) + self.jitter(rng)
+            })
+            .collect::<Vec<_>>();
+        self.nodes
+            .iter_mut()
+            .zip(forces)
+            .for_each(|(node, force)| *node += force);
+        self.split();
+    }
+
+    fn repulsion_at(&self, grid: &HashMap<(i64, i64), Vec<usize>>, i: usize) -> V2 {
+        let node = self.nodes[i];
+        let (column, row) = self.cell(node);
+        let mut force = V2::zero();
+        for neighbor_cell in iproduct!(
+            column.saturating_sub(1)..=column.saturating_add(1),
+            row.saturating_sub(1)..=row.saturating_add(1)
+        ) {
+            for &j in grid.get(&neighbor_cell).into_iter().flatten() {
+                let away = node - self.nodes[j];
+                let distance = away.length();
+                if j != i && distance < self.repulsion_radius && distance > 0. {
+                    let strength = 1. - distance / self.repulsion_radius;
+                    force += away / distance * strength * self.repulsion * self.repulsion_radius;
+                }
+            }
+        }
+        force
+    }
+
+    fn attraction_at(&self, i: usize) -> V2 {
+        let n = self.nodes.len();
+        let (previous, next) = match (i, self.closed) {
+            (_, true) if n > 2 => ((i + n - 1) % n, (i + 1) % n),
+            (i, false) if i > 0 && i + 1 < n => (i - 1, i + 1),
+            _ => return V2::zero(),
+        };
+        let midpoint = self.nodes[previous].lerp(self.nodes[next], 0.5);
+        (midpoint - self.nodes[i]) * self.attraction
+    }
+
+    fn jitter(&self, rng: &mut (impl Rng + ?Sized)) -> V2 {
+        if !valid(self.jitter) {
+            return V2::zero();
+        }
+        V2::new(
+            rng.gen_range(-self.jitter, self.jitter),
+            rng.gen_range(-self.jitter, self.jitter),
+        )
+    }
+
+    fn split(&mut self) {
+        if !valid(self.split_length) {
+            return;
+        }
+        let n = self.nodes.len();
+        let segments = if self.closed { n } else { n.saturating_sub(1) };
+        let mut split = Vec::with_capacity(n * 2);
+        for i in 0..n {
+            let a = self.nodes[i];
+            split.push(a);
+            if i < segments {
+                let b = self.nodes[(i + 1) % n];
+                if (b - a).length() > self.split_length {
+                    split.push(a.lerp(b, 0.5));
+                }
+            }
+        }
+        self.nodes = split;
+    }
+
+    /// Buckets node indices by cells as wide as the repulsion radius, so all nodes within the
+    /// radius of a node are in its cell or one of the eight around it.
+    fn grid(&self) -> HashMap<(i64, i64), Vec<usize>> {
+        let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            grid.entry(self.cell(*node)).or_default().push(i);
+        }
+        grid
+    }
+
+    fn cell(&self, p: P2) -> (i64, i64) {
+        (
+            (p.x / self.repulsion_radius).floor() as i64,
+            (p.y / self.repulsion_radius).floor() as i64,
+        )
+    }
+}
+
+/// Returns whether a parameter is positive and finite, as the simulation needs them to be in use.
+fn valid(parameter: f32) -> bool {
+    parameter > 0. && parameter.is_finite()
+}
+
+impl Paint for DifferentialGrowth {
+    fn paint(&self, canvas: &mut Canvas) {
+        canvas.paint(FlatIterPath::new(self.nodes(), self.closed))
+    }
+}
+
+impl Paint for &DifferentialGrowth {
+    fn paint(&self, canvas: &mut Canvas) {
+        (**self).paint(canvas);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Angle, Ellipse, PI};
+    use lyon_path::geom::LineSegment;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn growth_does_not_cross_itself() {
+        let start = (0..30).map(|i| {
+            Ellipse::circle(P2::new(0., 0.), 10.)
+                .circumpoint(Angle::radians(i as f32 / 30. * PI * 2.))
+        });
+        let mut growth = DifferentialGrowth::new(start, true)
+            .with_repulsion(4., 0.1)
+            .with_split_length(2.5);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut counts = vec![];
+        for _ in 0..4 {
+            (0..50).for_each(|_| growth.step(&mut rng));
+            counts.push(growth.nodes().count());
+        }
+        assert!(counts.windows(2).all(|w| w[1] > w[0]));
+
+        let nodes = growth.nodes().collect::<Vec<_>>();
+        let n = nodes.len();
+        let segment = |i: usize| LineSegment {
+            from: nodes[i],
+            to: nodes[(i + 1) % n],
+        };
+        for i in 0..n {
+            for j in i + 2..n {
+                if (j + 1) % n != i {
+                    assert!(!segment(i).intersects(&segment(j)), "{} crosses {}", i, j);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn growth_is_reproducible_from_a_seed() {
+        // Each run hashes into a freshly seeded grid, so this also checks that no output
+        // depends on the grid's iteration order.
+        let grow = || {
+            let start = (0..30).map(|i| {
+                Ellipse::circle(P2::new(0., 0.), 10.)
+                    .circumpoint(Angle::radians(i as f32 / 30. * PI * 2.))
+            });
+            let mut growth = DifferentialGrowth::new(start, true)
+                .with_repulsion(4., 0.1)
+                .with_split_length(2.5)
+                .with_jitter(0.2);
+            let mut rng = StdRng::seed_from_u64(7);
+            (0..100).for_each(|_| growth.step(&mut rng));
+            growth
+                .nodes()
+                .map(|p| (p.x.to_bits(), p.y.to_bits()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(grow(), grow());
+    }
+
+    #[test]
+    fn invalid_parameters_turn_forces_off() {
+        let start = vec![P2::new(0., 0.), P2::new(1., 0.), P2::new(2., 0.)];
+        for bad in &[0., -1., f32::NAN, f32::INFINITY] {
+            let mut growth = DifferentialGrowth::new(start.clone(), false)
+                .with_repulsion(*bad, 0.5)
+                .with_split_length(*bad)
+                .with_jitter(*bad);
+            let mut rng = StdRng::seed_from_u64(0);
+            (0..10).for_each(|_| growth.step(&mut rng));
+            // With only attraction left, a straight line stays put.
+            assert_eq!(growth.nodes().collect::<Vec<_>>(), start);
+        }
+
+        // Nodes far from the origin land in cells at the ends of the grid without overflowing.
+        let far = vec![P2::new(1e18, 0.), P2::new(-1e18, 1.)];
+        let mut growth = DifferentialGrowth::new(far.clone(), false)
+            .with_repulsion(1e-30, 0.1)
+            .with_split_length(1e38)
+            .with_jitter(0.);
+        growth.step(&mut StdRng::seed_from_u64(0));
+        assert_eq!(growth.nodes().collect::<Vec<_>>(), far);
+    }
+}
@@ -0,0 +1,227 @@
+//! L-systems.
+
+use crate::{Angle, Polyline, TransformStack, P2, V2};
+use rand::Rng;
+use std::collections::BTreeMap;
+
+/// A string rewriting system.
+///
+/// Each iteration replaces every symbol with a production rule by that rule's replacement, and
+/// leaves other symbols as they are. A symbol with several rules is stochastic: each time it is
+/// rewritten, one of its rules is chosen at random by weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LSystem {
+    pub axiom: String,
+    pub iterations: usize,
+    rules: BTreeMap<char, Vec<(f32, String)>>,
+}
+
+impl LSystem {
+    pub fn new(axiom: impl Into<String>, iterations: usize) -> Self {
+        Self {
+            axiom: axiom.into(),
+            iterations,
+            rules: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a rule rewriting `symbol` to `replacement`.
+    pub fn with_rule(self, symbol: char, replacement: impl Into<String>) -> Self {
+        self.with_weighted_rule(symbol, 1., replacement)
+    }
+
+    /// Adds a rule rewriting `symbol` to `replacement`, chosen with the given weight among the
+    /// other rules for `symbol`.
+    ///
+    /// Weights which are negative or not finite count as zero, and rules are chosen uniformly if
+    /// every rule for a symbol weighs zero.
+    pub fn with_weighted_rule(
+        mut self,
+        symbol: char,
+        weight: f32,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.rules
+            .entry(symbol)
+            .or_default()
+            .push((weight, replacement.into()));
+        self
+    }
+
+    /// Expands the axiom. Randomness is only drawn to rewrite symbols with several rules.
+    pub fn expand(&self, rng: &mut (impl Rng + ?Sized)) -> String {
+        (0..self.iterations).fold(self.axiom.clone(), |commands, _| {
+            commands.chars().fold(String::new(), |mut next, symbol| {
+                match self.rules.get(&symbol).map(Vec::as_slice) {
+                    None | Some([]) => next.push(symbol),
+                    Some([(_, replacement)]) => next.push_str(replacement),
+                    Some(rules) => next.push_str(choose(rules, rng)),
+                }
+                next
+            })
+        })
+    }
+}
+
+fn choose<'a>(rules: &'a [(f32, String)], rng: &mut (impl Rng + ?Sized)) -> &'a str {
+    let weight = |w: f32| if w > 0. && w.is_finite() { w } else { 0. };
+    let total: f32 = rules.iter().map(|(w, _)| weight(*w)).sum();
+    if !(total > 0. && total.is_finite()) {
+        return &rules[rng.gen_range(0, rules.len())].1;
+    }
+    let mut choice = rng.gen_range(0., total);
+    for (w, replacement) in rules {
+        if choice < weight(*w) {
+            return replacement;
+        }
+        choice -= weight(*w);
+    }
+    &rules[rules.len() - 1].1
+}
+
+/// A turtle which interprets command strings, such as those expanded by an `LSystem`, as paths.
+///
+/// Commands:
+///
+///   * `F` and `G` move forward by one step, drawing a line.
+///
+///   * `f` moves forward by one step without drawing.
+///
+///   * `+` and `-` turn counterclockwise and clockwise by the turn angle.
+///
+///   * `[` and `]` save and restore the turtle's position and heading.
+///
+/// All other symbols are ignored.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Turtle {
+    pub start: P2,
+    pub heading: Angle,
+    pub step: f32,
+    pub turn: Angle,
+}
+
+impl Turtle {
+    pub fn new(start: P2, step: f32, turn: Angle) -> Self {
+        Self {
+            start,
+            heading: Angle::radians(0.),
+            step,
+            turn,
+        }
+    }
+
+    pub fn with_heading(self, heading: Angle) -> Self {
+        Self { heading, ..self }
+    }
+
+    /// Walks the commands and returns the drawn lines.
+    pub fn interpret(&self, commands: &str) -> Vec<Polyline> {
+        let mut transforms = TransformStack::default();
+        transforms.translate(self.start.to_vector());
+        transforms.rotate(self.heading);
+
+        let origin = P2::new(0., 0.);
+        let mut lines = vec![];
+        let mut line = vec![transforms.apply(origin)];
+        let mut end_line = |line: &mut Vec<P2>, at: P2| {
+            let finished = std::mem::replace(line, vec![at]);
+            if finished.len() > 1 {
+                lines.push(finished.into_iter().collect());
+            }
+        };
+
+        for command in commands.chars() {
+            match command {
+                'F' | 'G' => {
+                    transforms.translate(V2::new(self.step, 0.));
+                    line.push(transforms.apply(origin));
+                }
+                'f' => {
+                    transforms.translate(V2::new(self.step, 0.));
+                    end_line(&mut line, transforms.apply(origin));
+                }
+                '+' => transforms.rotate(self.turn),
+                '-' => transforms.rotate(-self.turn),
+                '[' => transforms.push(),
+                ']' => {
+                    transforms.pop();
+                    end_line(&mut line, transforms.apply(origin));
+                }
+                _ => {}
+            }
+        }
+        end_line(&mut line, origin);
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PI;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn expansion() {
+        let algae = |iterations| {
+            LSystem::new("A", iterations)
+                .with_rule('A', "AB")
+                .with_rule('B', "A")
+                .expand(&mut StdRng::seed_from_u64(0))
+        };
+
+        assert_eq!(algae(0), "A");
+        assert_eq!(algae(1), "AB");
+        assert_eq!(algae(4), "ABAABABA");
+        assert_eq!(algae(5), "ABAABABAABAAB");
+    }
+
+    #[test]
+    fn stochastic_expansion_is_seeded() {
+        let plant = LSystem::new("F", 4)
+            .with_weighted_rule('F', 1., "F[+F]F")
+            .with_weighted_rule('F', 1., "F[-F]F");
+        let expand = |seed| plant.expand(&mut StdRng::seed_from_u64(seed));
+
+        assert_eq!(expand(3), expand(3));
+        assert_ne!(expand(3), expand(4));
+        assert_eq!(expand(3).len(), 201);
+    }
+
+    #[test]
+    fn invalid_weights_are_ignored() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let unweighted = LSystem::new("FFFFFFFF", 1)
+            .with_weighted_rule('F', 0., "A")
+            .with_weighted_rule('F', 0., "B")
+            .expand(&mut rng);
+        assert!(unweighted.contains('A') && unweighted.contains('B'));
+
+        let weighted = LSystem::new("FFFFFFFF", 1)
+            .with_weighted_rule('F', -5., "A")
+            .with_weighted_rule('F', f32::NAN, "B")
+            .with_weighted_rule('F', 1., "C")
+            .expand(&mut rng);
+        assert_eq!(weighted, "CCCCCCCC");
+    }
+
+    #[test]
+    fn turtle_branches() {
+        let turtle = Turtle::new(P2::new(0., 0.), 1., Angle::radians(PI / 2.));
+        let lines = turtle.interpret("F[+F]F");
+
+        let vertices = lines
+            .iter()
+            .map(|l| {
+                l.vertices()
+                    .map(|p| (p.x.round() as i32, p.y.round() as i32))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vertices,
+            vec![vec![(0, 0), (1, 0), (1, 1)], vec![(1, 0), (2, 0)]]
+        );
+    }
+}
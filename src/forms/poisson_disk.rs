@@ -0,0 +1,190 @@
+//! Poisson disk sampling.
+
+use crate::{Angle, Bounds, P2, PI, V2};
+use rand::Rng;
+
+/// Returns points within the bounds for which `inside` is true, no two closer than
+/// `min_distance`, spread evenly as blue noise by Bridson's algorithm.
+///
+/// Sampling starts from one random point and grows outward, trying thirty candidates around
+/// each accepted point until no more fit. Candidates land up to twice the minimum distance away,
+/// so regions separated by a wider gap than that from the first point are left empty.
+///
+/// A `min_distance` which is not positive and finite, or bounds which are not finite, give no
+/// points.
+pub fn poisson_disk(
+    bounds: Bounds,
+    min_distance: f32,
+    rng: &mut (impl Rng + ?Sized),
+    inside: impl Fn(P2) -> bool,
+) -> Vec<P2> {
+    /// The candidates tried around each point before it is retired.
+    const CANDIDATES: usize = 30;
+    /// The random draws allowed for the first point.
+    const SEED_ATTEMPTS: usize = 1000;
+
+    let finite = |v: f32| v.is_finite();
+    if bounds.is_empty()
+        || !(min_distance > 0. && finite(min_distance * 2.))
+        || !(finite(bounds.min_x()) && finite(bounds.min_y()))
+        || !(finite(bounds.max_x()) && finite(bounds.max_y()))
+    {
+        return vec![];
+    }
+    let fits = |p: P2| {
+        p.x >= bounds.min_x()
+            && p.x < bounds.max_x()
+            && p.y >= bounds.min_y()
+            && p.y < bounds.max_y()
+            && inside(p)
+    };
+    let seed = (0..SEED_ATTEMPTS)
+        .map(|_| {
+            P2::new(
+                rng.gen_range(bounds.min_x(), bounds.max_x()),
+                rng.gen_range(bounds.min_y(), bounds.max_y()),
+            )
+        })
+        .find(|p| fits(*p));
+    let seed = match seed {
+        Some(seed) => seed,
+        None => return vec![],
+    };
+
+    let mut grid = Grid::new(bounds, min_distance);
+    let mut points = vec![seed];
+    let mut active = vec![0];
+    grid.insert(seed, 0);
+    while !active.is_empty() {
+        let a = rng.gen_range(0, active.len());
+        let center = points[active[a]];
+        let candidate = (0..CANDIDATES)
+            .map(|_| {
+                let angle = Angle::radians(rng.gen_range(0., PI * 2.));
+                let distance = rng.gen_range(min_distance, min_distance * 2.);
+                center + V2::from_angle_and_length(angle, distance)
+            })
+            .find(|p| {
+                fits(*p)
+                    && grid
+                        .neighbors(*p)
+                        .all(|i| (points[i] - *p).length() >= min_distance)
+            });
+        match candidate {
+            Some(p) => {
+                grid.insert(p, points.len());
+                active.push(points.len());
+                points.push(p);
+            }
+            None => {
+                active.swap_remove(a);
+            }
+        }
+    }
+
+    points
+}
+
+/// A grid over the bounds holding points by index.
+///
+/// Cells are small enough to hold at most one point each, unless that would take more than
+/// `MAX_CELLS` cells, in which case they grow to fit the bounds in that many. Any point closer
+/// than the minimum distance to a query is within `reach` cells of it.
+struct Grid {
+    bounds: Bounds,
+    cell_size: f32,
+    reach: usize,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    /// The most cells a grid allocates.
+    const MAX_CELLS: f32 = (1 << 20) as f32;
+
+    fn new(bounds: Bounds, min_distance: f32) -> Self {
+        let area = bounds.size.width * bounds.size.height;
+        let cell_size = (min_distance / 2f32.sqrt()).max((area / Self::MAX_CELLS).sqrt());
+        let columns = (bounds.size.width / cell_size).ceil().max(1.) as usize;
+        let rows = (bounds.size.height / cell_size).ceil().max(1.) as usize;
+        Self {
+            bounds,
+            cell_size,
+            reach: (min_distance / cell_size).ceil() as usize,
+            columns,
+            rows,
+            cells: vec![vec![]; columns * rows],
+        }
+    }
+
+    fn cell(&self, p: P2) -> (usize, usize) {
+        let column = ((p.x - self.bounds.min_x()) / self.cell_size) as usize;
+        let row = ((p.y - self.bounds.min_y()) / self.cell_size) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    fn insert(&mut self, p: P2, index: usize) {
+        let (column, row) = self.cell(p);
+        self.cells[row * self.columns + column].push(index);
+    }
+
+    fn neighbors<'a>(&'a self, p: P2) -> impl Iterator<Item = usize> + 'a {
+        let (column, row) = self.cell(p);
+        let reach = self.reach;
+        let columns = column.saturating_sub(reach)..(column + reach + 1).min(self.columns);
+        let rows = row.saturating_sub(reach)..(row + reach + 1).min(self.rows);
+        rows.flat_map(move |row| {
+            columns
+                .clone()
+                .flat_map(move |column| self.cells[row * self.columns + column].iter().copied())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Ellipse, S2};
+    use itertools::Itertools;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn points_are_spread_apart() {
+        let bounds = Bounds::new(P2::new(-50., -50.), S2::new(100., 100.));
+        let circle = Ellipse::circle(P2::new(0., 0.), 40.);
+        let inside = |p: P2| (p - circle.center).length() < circle.radii.x;
+        let points = poisson_disk(bounds, 5., &mut StdRng::seed_from_u64(0), inside);
+
+        // Even spacing packs well over a hundred points into the circle.
+        assert!(points.len() > 100, "{}", points.len());
+        assert!(points.iter().all(|p| inside(*p)));
+        for (a, b) in points.iter().tuple_combinations() {
+            assert!((*a - *b).length() >= 5.);
+        }
+    }
+
+    #[test]
+    fn invalid_distances_sample_nothing() {
+        let bounds = Bounds::new(P2::new(0., 0.), S2::new(10., 10.));
+        for distance in &[0., -1., f32::NAN, f32::INFINITY, f32::MAX] {
+            let points = poisson_disk(bounds, *distance, &mut StdRng::seed_from_u64(0), |_| true);
+            assert!(points.is_empty(), "{}", distance);
+        }
+    }
+
+    #[test]
+    fn grids_are_capped() {
+        let bounds = Bounds::new(P2::new(0., 0.), S2::new(1e4, 1e4));
+        let grid = Grid::new(bounds, 1e-3);
+        assert!(grid.columns * grid.rows <= Grid::MAX_CELLS as usize);
+
+        // Coarser cells still find every point within the minimum distance.
+        let mut grid = Grid::new(bounds, 1e-3);
+        let (a, b) = (P2::new(5., 5.), P2::new(5.0009, 5.));
+        grid.insert(a, 0);
+        assert!(grid.neighbors(b).any(|i| i == 0));
+        grid.insert(b, 1);
+        assert_eq!(grid.neighbors(a).count(), 2);
+    }
+}
@@ -1,8 +1,12 @@
 //! Polygon.
 
-use crate::{Angle, Canvas, FlatIterPath, Paint, Rotate, Subdivide, Translate, P2, V2};
+use crate::{
+    poisson_disk, Angle, Bounds, Canvas, FillRule, FlatIterPath, Paint, Rotate, Subdivide,
+    Translate, P2, V2,
+};
 use arrayvec::ArrayVec;
 use itertools::Itertools;
+use rand::Rng;
 use std::iter::{DoubleEndedIterator, FromIterator};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -30,6 +34,336 @@ impl Polygon {
             .chain(last_iter)
             .tuple_windows()
     }
+
+    /// Returns the polygon with its vertices in the opposite order, which flips its winding.
+    pub fn reversed(mut self) -> Self {
+        self.vertices.reverse();
+        self
+    }
+
+    /// Returns the smallest axis aligned bounds containing the polygon's vertices.
+    ///
+    /// Combine bounds with `Bounds::union` and `Bounds::intersection`.
+    pub fn bounds(&self) -> Bounds {
+        Bounds::from_points(&self.vertices)
+    }
+
+    /// Returns whether the point is inside the polygon under the fill rule.
+    ///
+    /// This counts the edges a ray from the point toward +x crosses. A point on the boundary is
+    /// inside only if the polygon lies toward +x or +y of it, so polygons sharing an edge never
+    /// both contain a point on it, as for pixels that tile without overlapping.
+    pub fn contains(&self, p: P2, fill_rule: FillRule) -> bool {
+        let winding: i32 = self
+            .vertices()
+            .zip(self.vertices().cycle().skip(1))
+            .filter(|(a, b)| (a.y <= p.y) != (b.y <= p.y))
+            .filter(|(a, b)| a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) > p.x)
+            .map(|(a, b)| if b.y > a.y { 1 } else { -1 })
+            .sum();
+        match fill_rule {
+            FillRule::EvenOdd => winding & 1 == 1,
+            FillRule::NonZero => winding != 0,
+        }
+    }
+
+    /// Returns up to `n` points drawn uniformly from inside the polygon under the fill rule,
+    /// for stippling.
+    ///
+    /// Points are drawn in the polygon's bounds and kept if the polygon contains them. Drawing
+    /// gives up after a hundred tries per point asked for, so a polygon covering little of its
+    /// bounds, or none of it, returns fewer points rather than taking forever.
+    pub fn sample_interior(
+        &self,
+        rng: &mut (impl Rng + ?Sized),
+        n: usize,
+        fill_rule: FillRule,
+    ) -> Vec<P2> {
+        /// The draws allowed per point asked for before giving up.
+        const ATTEMPTS_PER_POINT: usize = 100;
+
+        let bounds = self.bounds();
+        if bounds.is_empty() {
+            return vec![];
+        }
+        (0..n * ATTEMPTS_PER_POINT)
+            .map(|_| {
+                P2::new(
+                    rng.gen_range(bounds.min_x(), bounds.max_x()),
+                    rng.gen_range(bounds.min_y(), bounds.max_y()),
+                )
+            })
+            .filter(|p| self.contains(*p, fill_rule))
+            .take(n)
+            .collect()
+    }
+
+    /// Returns points inside the polygon under the fill rule, no two closer than
+    /// `min_distance`, by Poisson disk sampling. See `poisson_disk`.
+    pub fn poisson_disk(
+        &self,
+        rng: &mut (impl Rng + ?Sized),
+        min_distance: f32,
+        fill_rule: FillRule,
+    ) -> Vec<P2> {
+        poisson_disk(self.bounds(), min_distance, rng, |p| {
+            self.contains(p, fill_rule)
+        })
+    }
+
+    /// Returns the length of the polygon's boundary, including the edge closing it.
+    pub fn perimeter(&self) -> f32 {
+        self.vertices()
+            .chain(self.vertices().take(1))
+            .tuple_windows()
+            .map(|(a, b)| (b - a).length())
+            .sum()
+    }
+
+    /// Returns the polygon grown outward by `distance`, or shrunk inward if it is negative, with
+    /// each edge moved that far along its normal and neighboring edges extended or trimmed to
+    /// meet again.
+    ///
+    /// Corners pointing the way the polygon moves are mitered, so a square grows into a larger
+    /// square. The miter of a sharp corner reaches far past it, so corners whose miter would be
+    /// over four times the distance from the corner are beveled with two vertices instead, as are
+    /// vertices where the outline doubles back on itself.
+    /// Insets past the polygon's narrowest width overlap themselves; this does not remove the
+    /// loops that leaves.
+    pub fn offset(&self, distance: f32) -> Self {
+        /// The longest miter, as a multiple of the distance, before a corner is beveled.
+        const MITER_LIMIT: f32 = 4.;
+
+        let mut vertices = self.vertices.clone();
+        vertices.dedup();
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        if vertices.len() < 3 || distance == 0. {
+            return Self { vertices };
+        }
+
+        let area: f32 = vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum();
+        // The outward normal is to the right of each edge when the signed area is positive.
+        let outward = if area > 0. { distance } else { -distance };
+        let normal = |a: P2, b: P2| {
+            let edge = (b - a).normalize();
+            V2::new(edge.y, -edge.x)
+        };
+
+        let n = vertices.len();
+        let mut offset = Vec::with_capacity(n);
+        for i in 0..n {
+            let (previous, vertex, next) = (
+                vertices[(i + n - 1) % n],
+                vertices[i],
+                vertices[(i + 1) % n],
+            );
+            let (n1, n2) = (normal(previous, vertex), normal(vertex, next));
+            let turn = (vertex - previous).cross(next - vertex);
+            let cos = 1. + n1.dot(n2);
+            // Where the outline doubles back on itself, the edges' offsets never meet, so it is
+            // beveled whichever way the polygon moves.
+            if turn * outward >= 0. && cos < 2. / (MITER_LIMIT * MITER_LIMIT) {
+                offset.push(vertex + n1 * outward);
+                offset.push(vertex + n2 * outward);
+            } else {
+                offset.push(vertex + (n1 + n2) * (outward / cos));
+            }
+        }
+        Self { vertices: offset }
+    }
+
+    /// Returns the part of the polygon inside the bounds, with vertices added where its edges
+    /// cross theirs. A polygon inside the bounds is unchanged, and one outside them has no
+    /// vertices left.
+    ///
+    /// This is Sutherland-Hodgman clipping, so a concave polygon which leaves and reenters the
+    /// bounds stays one polygon, joined by edges running along the bounds.
+    pub fn clip(&self, bounds: Bounds) -> Self {
+        let planes: [&dyn Fn(P2) -> f32; 4] = [
+            &|p| p.x - bounds.min_x(),
+            &|p| bounds.max_x() - p.x,
+            &|p| p.y - bounds.min_y(),
+            &|p| bounds.max_y() - p.y,
+        ];
+        let vertices = planes
+            .iter()
+            .fold(self.vertices.clone(), |vertices, inside| {
+                let mut clipped = Vec::with_capacity(vertices.len() + 1);
+                let next = vertices.iter().cycle().skip(1);
+                for (a, b) in vertices.iter().copied().zip(next.copied()) {
+                    let (da, db) = (inside(a), inside(b));
+                    if da >= 0. {
+                        clipped.push(a);
+                    }
+                    if (da >= 0.) != (db >= 0.) {
+                        clipped.push(a.lerp(b, da / (da - db)));
+                    }
+                }
+                clipped
+            });
+        Self { vertices }
+    }
+
+    /// Returns the regions inside both this polygon and the other.
+    ///
+    /// This is Greiner-Hormann clipping, scoped to simple polygons: neither may cross itself.
+    /// Either may wind either way. Where the boundaries touch, such as cells of a grid sharing
+    /// edges, the other polygon is nudged a hundred-thousandth of their extent to decide which
+    /// side of each other the boundaries run on, and crossings are placed where the unmoved edges
+    /// meet, so shared edges and vertices come out where they were. Features smaller than the
+    /// nudge may be misjudged. Polygons with non-finite vertices have no intersection.
+    pub fn intersection(&self, other: &Polygon) -> Vec<Polygon> {
+        /// How far the other polygon is nudged, as a fraction of the extent of both.
+        const NUDGE: f32 = 1e-5;
+
+        let finite = |p: &Polygon| p.vertices().all(|v| v.x.is_finite() && v.y.is_finite());
+        if self.vertices.len() < 3 || other.vertices.len() < 3 || !finite(self) || !finite(other) {
+            return vec![];
+        }
+        let extent = self.bounds().union(&other.bounds());
+        let nudge = extent.size.width.max(extent.size.height) * NUDGE;
+        // The golden ratio keeps the nudge off the axes and diagonals edges often run along.
+        let nudged = other.clone().translate(V2::new(nudge, nudge * 0.618_034));
+        let polygons = [self, &nudged];
+        let crossings = crossings(&self.vertices, &other.vertices, &nudged.vertices);
+        if crossings.is_empty() {
+            // Without crossings, the boundaries are either nested or apart.
+            let inside = |a: &Polygon, b: &Polygon| {
+                a.vertices()
+                    .take(1)
+                    .any(|v| b.contains(v, FillRule::NonZero))
+            };
+            return match (inside(self, &nudged), inside(&nudged, self)) {
+                (true, _) => vec![self.clone()],
+                (_, true) => vec![other.clone()],
+                _ => vec![],
+            };
+        }
+
+        // Each polygon's unmoved vertices with the crossings along each edge spliced in order,
+        // the position of each crossing in them, and whether following the polygon forward from
+        // each crossing enters the other.
+        let mut lists: [Vec<(P2, Option<usize>)>; 2] = [vec![], vec![]];
+        let mut positions = [vec![0; crossings.len()], vec![0; crossings.len()]];
+        let mut entries = [vec![false; crossings.len()], vec![false; crossings.len()]];
+        for side in 0..2 {
+            let vertices = &[self, other][side].vertices;
+            let first = polygons[side].vertices[0];
+            let mut inside = polygons[1 - side].contains(first, FillRule::NonZero);
+            for (edge, vertex) in vertices.iter().enumerate() {
+                lists[side].push((*vertex, None));
+                let on_edge = crossings
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| c.edges[side] == edge)
+                    .sorted_by(|(_, a), (_, b)| a.along[side].total_cmp(&b.along[side]))
+                    .collect::<Vec<_>>();
+                for (k, crossing) in on_edge {
+                    positions[side][k] = lists[side].len();
+                    entries[side][k] = !inside;
+                    inside = !inside;
+                    lists[side].push((crossing.point, Some(k)));
+                }
+            }
+        }
+
+        let mut visited = vec![false; crossings.len()];
+        let mut regions = vec![];
+        for start in 0..crossings.len() {
+            if visited[start] {
+                continue;
+            }
+            let mut region: Vec<P2> = vec![];
+            let (mut side, mut k) = (0, start);
+            loop {
+                visited[k] = true;
+                let list = &lists[side];
+                let n = list.len();
+                let mut i = positions[side][k];
+                region.push(list[i].0);
+                let forward = entries[side][k];
+                // Follow this polygon inside the other until it crosses out again.
+                loop {
+                    i = if forward {
+                        (i + 1) % n
+                    } else {
+                        (i + n - 1) % n
+                    };
+                    match list[i] {
+                        (_, Some(next)) => {
+                            k = next;
+                            break;
+                        }
+                        (point, None) => region.push(point),
+                    }
+                }
+                side = 1 - side;
+                // Reaching a crossing already walked through early means the entry flags were
+                // inconsistent, so stop rather than loop forever.
+                if k == start || visited[k] {
+                    break;
+                }
+            }
+            region.dedup();
+            if region.len() > 1 && region.first() == region.last() {
+                region.pop();
+            }
+            if region.len() > 2 {
+                regions.push(Self { vertices: region });
+            }
+        }
+        regions
+    }
+}
+
+/// A point where an edge of one polygon crosses an edge of another.
+struct Crossing {
+    point: P2,
+    /// The index of the crossed edge in each polygon, each from the vertex of that index.
+    edges: [usize; 2],
+    /// How far along each edge the crossing is, from 0 at its start to 1 at its end.
+    along: [f32; 2],
+}
+
+/// Returns every point where an edge of `a` crosses an edge of `nudged`, a copy of `b` moved
+/// slightly, leaving out edges which run parallel. Crossings are placed where the lines through
+/// the edges of `a` and `b` meet.
+fn crossings(a: &[P2], b: &[P2], nudged: &[P2]) -> Vec<Crossing> {
+    let edges = |vertices: &[P2]| {
+        vertices
+            .iter()
+            .copied()
+            .zip(vertices.iter().copied().cycle().skip(1))
+            .collect::<Vec<_>>()
+    };
+    let (a_edges, b_edges, nudged_edges) = (edges(a), edges(b), edges(nudged));
+    let mut crossings = vec![];
+    for (i, (a0, a1)) in a_edges.iter().enumerate() {
+        for (j, ((b0, b1), (n0, _))) in b_edges.iter().zip(&nudged_edges).enumerate() {
+            let (r, s) = (*a1 - *a0, *b1 - *b0);
+            let denominator = r.cross(s);
+            if denominator == 0. {
+                continue;
+            }
+            let t = (*n0 - *a0).cross(s) / denominator;
+            let u = (*n0 - *a0).cross(r) / denominator;
+            if t > 0. && t < 1. && u > 0. && u < 1. {
+                let exact = (*b0 - *a0).cross(s) / denominator;
+                crossings.push(Crossing {
+                    point: *a0 + r * exact,
+                    edges: [i, j],
+                    along: [t, u],
+                });
+            }
+        }
+    }
+    crossings
 }
 
 impl Paint for Polygon {
@@ -97,3 +431,270 @@ impl FromIterator<P2> for Polygon {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::S2;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn polygon(points: &[(f32, f32)]) -> Polygon {
+        points.iter().map(|&(x, y)| P2::new(x, y)).collect()
+    }
+
+    #[test]
+    fn polygon_perimeter() {
+        let square = vec![
+            P2::new(0., 0.),
+            P2::new(2., 0.),
+            P2::new(2., 2.),
+            P2::new(0., 2.),
+        ]
+        .into_iter()
+        .collect::<Polygon>();
+        assert_eq!(square.perimeter(), 8.);
+    }
+
+    #[test]
+    fn polygon_contains() {
+        // A U shape, open at the top.
+        let u = vec![
+            P2::new(0., 0.),
+            P2::new(3., 0.),
+            P2::new(3., 3.),
+            P2::new(2., 3.),
+            P2::new(2., 1.),
+            P2::new(1., 1.),
+            P2::new(1., 3.),
+            P2::new(0., 3.),
+        ]
+        .into_iter()
+        .collect::<Polygon>();
+        for &rule in &[FillRule::EvenOdd, FillRule::NonZero] {
+            assert!(u.contains(P2::new(0.5, 2.), rule));
+            assert!(u.contains(P2::new(2.5, 2.), rule));
+            assert!(u.contains(P2::new(1.5, 0.5), rule));
+            assert!(!u.contains(P2::new(1.5, 2.), rule));
+            assert!(!u.contains(P2::new(-1., 0.5), rule));
+            assert!(!u.contains(P2::new(4., 0.5), rule));
+
+            // Vertices and horizontal edges fall on the same side every time.
+            assert!(u.contains(P2::new(0., 0.), rule));
+            assert!(u.contains(P2::new(1.5, 0.), rule));
+            assert!(!u.contains(P2::new(3., 0.), rule));
+            assert!(!u.contains(P2::new(0.5, 3.), rule));
+            assert!(!u.contains(P2::new(1.5, 1.), rule));
+        }
+
+        // A pentagram winds around its center twice.
+        let star = (0..5)
+            .map(|i| {
+                let angle = i as f32 * 4. * std::f32::consts::PI / 5.;
+                P2::new(angle.cos(), angle.sin())
+            })
+            .collect::<Polygon>();
+        assert!(!star.contains(P2::new(0., 0.), FillRule::EvenOdd));
+        assert!(star.contains(P2::new(0., 0.), FillRule::NonZero));
+    }
+
+    #[test]
+    fn polygon_bounds() {
+        let triangle = polygon(&[(1., 1.), (4., 2.), (2., 5.)]);
+        assert_eq!(
+            triangle.bounds(),
+            Bounds::new(P2::new(1., 1.), S2::new(3., 4.))
+        );
+
+        let nested = polygon(&[(2., 2.), (3., 2.), (3., 3.)]).bounds();
+        assert_eq!(triangle.bounds().union(&nested), triangle.bounds());
+        assert_eq!(triangle.bounds().intersection(&nested), Some(nested));
+
+        let disjoint = polygon(&[(10., 10.), (11., 10.), (11., 12.)]).bounds();
+        assert_eq!(
+            triangle.bounds().union(&disjoint),
+            Bounds::new(P2::new(1., 1.), S2::new(10., 11.))
+        );
+        assert_eq!(triangle.bounds().intersection(&disjoint), None);
+    }
+
+    #[test]
+    fn polygon_samples_interior() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let triangle = polygon(&[(0., 0.), (10., 0.), (0., 10.)]);
+        let points = triangle.sample_interior(&mut rng, 500, FillRule::NonZero);
+        assert_eq!(points.len(), 500);
+        assert!(points
+            .iter()
+            .all(|p| triangle.contains(*p, FillRule::NonZero)));
+        // Uniform points fall on either side of the line splitting the triangle in half.
+        let near = points
+            .iter()
+            .filter(|p| p.x + p.y < 10. / 2f32.sqrt())
+            .count();
+        assert!((200..300).contains(&near), "{}", near);
+
+        let sliver = polygon(&[(0., 0.), (100., 100.), (99.99, 100.)]);
+        assert!(
+            sliver
+                .sample_interior(&mut rng, 10, FillRule::NonZero)
+                .len()
+                < 10
+        );
+        let line = polygon(&[(0., 0.), (10., 0.)]);
+        assert!(line
+            .sample_interior(&mut rng, 10, FillRule::NonZero)
+            .is_empty());
+    }
+
+    #[test]
+    fn polygon_poisson_disk() {
+        let triangle = vec![P2::new(0., 0.), P2::new(50., 0.), P2::new(0., 50.)]
+            .into_iter()
+            .collect::<Polygon>();
+        let points = triangle.poisson_disk(&mut StdRng::seed_from_u64(0), 3., FillRule::NonZero);
+        assert!(points.len() > 20);
+        assert!(points
+            .iter()
+            .all(|p| triangle.contains(*p, FillRule::NonZero)));
+        for (a, b) in points.iter().tuple_combinations() {
+            assert!((*a - *b).length() >= 3.);
+        }
+    }
+
+    #[test]
+    fn polygon_offsets() {
+        let square = polygon(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        let grown = Bounds::new(P2::new(-1., -1.), S2::new(12., 12.));
+        let bounds = |p: Polygon| {
+            assert_eq!(p.vertices().count(), 4);
+            p.bounds()
+        };
+        assert_eq!(bounds(square.offset(1.)), grown);
+        assert_eq!(bounds(square.clone().reversed().offset(1.)), grown);
+        assert_eq!(grown.center(), square.bounds().center());
+        assert_eq!(
+            bounds(square.offset(-2.)),
+            Bounds::new(P2::new(2., 2.), S2::new(6., 6.))
+        );
+        assert_eq!(square.offset(0.), square);
+
+        // The sharp tip is beveled, and the other two corners are mitered.
+        let spike = polygon(&[(0., 0.), (100., 5.), (0., 10.)]);
+        let offset = spike.offset(1.);
+        assert_eq!(offset.vertices().count(), 4);
+        assert!(offset.vertices().all(|v| v.x < 102.));
+        for v in spike.vertices() {
+            assert!(offset.vertices().any(|o| (o - v).length() < 4.));
+        }
+    }
+
+    #[test]
+    fn polygon_intersections() {
+        let area = |p: &Polygon| {
+            p.vertices()
+                .zip(p.vertices().cycle().skip(1))
+                .map(|(a, b)| a.x * b.y - b.x * a.y)
+                .sum::<f32>()
+                .abs()
+                / 2.
+        };
+        let areas = |regions: Vec<Polygon>| regions.iter().map(area).collect::<Vec<_>>();
+
+        let a = polygon(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        let b = polygon(&[(5., 5.), (15., 5.), (15., 15.), (5., 15.)]);
+        let overlap = a.intersection(&b);
+        assert_eq!(
+            overlap[0].bounds(),
+            Bounds::new(P2::new(5., 5.), S2::new(5., 5.))
+        );
+        assert_eq!(areas(overlap), vec![25.]);
+        assert_eq!(areas(b.intersection(&a.clone().reversed())), vec![25.]);
+
+        let u = polygon(&[
+            (0., 0.),
+            (10., 0.),
+            (10., 10.),
+            (7., 10.),
+            (7., 3.),
+            (3., 3.),
+            (3., 10.),
+            (0., 10.),
+        ]);
+        let bar = polygon(&[(-1., 5.), (11., 5.), (11., 8.), (-1., 8.)]);
+        assert_eq!(areas(u.intersection(&bar)), vec![9., 9.]);
+
+        let inner = polygon(&[(2., 2.), (4., 2.), (4., 4.)]);
+        assert_eq!(a.intersection(&inner), vec![inner.clone()]);
+        assert_eq!(inner.intersection(&a), vec![inner.clone()]);
+        let apart = polygon(&[(20., 20.), (30., 20.), (30., 30.)]);
+        assert!(a.intersection(&apart).is_empty());
+
+        // Cells of a grid share edges and corners.
+        let beside = polygon(&[(5., 0.), (15., 0.), (15., 10.), (5., 10.)]);
+        let shared = a.intersection(&beside);
+        assert_eq!(
+            shared[0].bounds(),
+            Bounds::new(P2::new(5., 0.), S2::new(5., 10.))
+        );
+        assert_eq!(areas(shared), vec![50.]);
+        assert_eq!(areas(beside.intersection(&a)), vec![50.]);
+        assert_eq!(areas(a.intersection(&a.clone())), vec![100.]);
+        let neighbor = polygon(&[(10., 0.), (20., 0.), (20., 10.), (10., 10.)]);
+        assert!(areas(a.intersection(&neighbor))
+            .iter()
+            .all(|area| *area < 0.01));
+        let corner = polygon(&[(10., 10.), (20., 10.), (20., 20.), (10., 20.)]);
+        assert!(areas(a.intersection(&corner))
+            .iter()
+            .all(|area| *area < 0.01));
+        let vertex_on_edge = polygon(&[(5., 10.), (8., 14.), (2., 14.)]);
+        assert!(areas(a.intersection(&vertex_on_edge))
+            .iter()
+            .all(|area| *area < 0.01));
+
+        let nan = polygon(&[(0., 0.), (f32::NAN, 0.), (0., 10.)]);
+        assert!(a.intersection(&nan).is_empty());
+    }
+
+    #[test]
+    fn polygon_offsets_where_outline_doubles_back() {
+        let spur = vec![
+            P2::new(0., 0.),
+            P2::new(10., 0.),
+            P2::new(10., 10.),
+            P2::new(5., 10.),
+            P2::new(5., 15.),
+            P2::new(5., 10.),
+            P2::new(0., 10.),
+        ]
+        .into_iter()
+        .collect::<Polygon>();
+        for distance in &[1., -1.] {
+            let offset = spur.offset(*distance);
+            assert!(offset
+                .vertices()
+                .all(|v| v.x.is_finite() && v.y.is_finite()));
+            // The tip of the spur is beveled square across its end.
+            let tip = offset.vertices().filter(|v| v.y == 15.).collect::<Vec<_>>();
+            assert_eq!(tip.len(), 2);
+            assert!(tip.contains(&P2::new(4., 15.)) && tip.contains(&P2::new(6., 15.)));
+        }
+    }
+
+    #[test]
+    fn polygon_clips() {
+        let bounds = Bounds::new(P2::new(0., 0.), S2::new(10., 10.));
+
+        let square = polygon(&[(5., 2.), (15., 2.), (15., 8.), (5., 8.)]);
+        assert_eq!(
+            square.clip(bounds),
+            polygon(&[(5., 2.), (10., 2.), (10., 8.), (5., 8.)])
+        );
+
+        let inside = polygon(&[(1., 1.), (4., 2.), (2., 5.)]);
+        assert_eq!(inside.clip(bounds), inside);
+
+        let outside = polygon(&[(11., 1.), (14., 2.), (12., 5.)]);
+        assert_eq!(outside.clip(bounds).vertices().count(), 0);
+    }
+}
@@ -0,0 +1,329 @@
+//! Polyline.
+
+use crate::{Angle, Bounds, Canvas, FlatIterPath, Paint, Rotate, Subdivide, Translate, P2, V2};
+use arrayvec::ArrayVec;
+use itertools::Itertools;
+use std::iter::{DoubleEndedIterator, FromIterator};
+
+/// An open path through a sequence of vertices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    vertices: Vec<P2>,
+}
+
+impl Polyline {
+    /// Returns an iterator over the polyline's vertices.
+    pub fn vertices<'a>(&'a self) -> impl DoubleEndedIterator<Item = P2> + Clone + 'a {
+        self.vertices.iter().copied()
+    }
+
+    /// Returns an iterator over unique references to the polyline's vertices.
+    pub fn vertices_mut<'a>(&'a mut self) -> impl DoubleEndedIterator<Item = &'a mut P2> + 'a {
+        self.vertices.iter_mut()
+    }
+
+    /// Returns the polyline traced from its last vertex to its first.
+    pub fn reversed(mut self) -> Self {
+        self.vertices.reverse();
+        self
+    }
+
+    /// Returns the smallest axis aligned bounds containing the polyline's vertices.
+    ///
+    /// Combine bounds with `Bounds::union` and `Bounds::intersection`.
+    pub fn bounds(&self) -> Bounds {
+        Bounds::from_points(&self.vertices)
+    }
+
+    /// Returns the length of the polyline along its vertices.
+    pub fn length(&self) -> f32 {
+        self.vertices()
+            .tuple_windows()
+            .map(|(a, b)| (b - a).length())
+            .sum()
+    }
+
+    /// Returns the point the given distance along the polyline from its first vertex, or none
+    /// if the distance is negative or past the end.
+    pub fn sample_at_distance(&self, distance: f32) -> Option<P2> {
+        if distance < 0. {
+            return None;
+        }
+        let mut remaining = distance;
+        for (a, b) in self.vertices().tuple_windows() {
+            let edge = (b - a).length();
+            if remaining <= edge {
+                let t = if edge > 0. { remaining / edge } else { 0. };
+                return Some(a.lerp(b, t));
+            }
+            remaining -= edge;
+        }
+        match self.vertices.len() {
+            1 if distance == 0. => self.vertices.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// Splits the polyline in two at the given distance along it, clamped to its length. The
+    /// first piece ends and the second starts at the split point, so together they trace the
+    /// same path.
+    pub fn split_at(&self, distance: f32) -> (Self, Self) {
+        let mut first = vec![];
+        let mut remaining = distance.max(0.);
+        for (i, (a, b)) in self.vertices().tuple_windows().enumerate() {
+            first.push(a);
+            let edge = (b - a).length();
+            if remaining < edge {
+                let split = a.lerp(b, remaining / edge);
+                first.push(split);
+                let second = std::iter::once(split).chain(self.vertices().skip(i + 1));
+                return (Self { vertices: first }, second.collect());
+            }
+            remaining -= edge;
+        }
+        let last = self.vertices().last();
+        (
+            self.clone(),
+            Self {
+                vertices: last.into_iter().collect(),
+            },
+        )
+    }
+
+    /// Returns the pieces of the polyline inside the bounds, with a vertex added wherever it
+    /// crosses their edges. A polyline inside the bounds is returned whole, and one outside them
+    /// returns no pieces.
+    pub fn clip(&self, bounds: Bounds) -> Vec<Self> {
+        if self.vertices.len() < 2 {
+            return match self.vertices.first() {
+                Some(&v) if clip_segment(v, v, bounds).is_some() => vec![self.clone()],
+                _ => vec![],
+            };
+        }
+        let mut pieces = vec![];
+        let mut piece = vec![];
+        for (a, b) in self.vertices().tuple_windows() {
+            match clip_segment(a, b, bounds) {
+                Some((t0, t1)) => {
+                    if t0 > 0. || piece.is_empty() {
+                        pieces.push(std::mem::take(&mut piece));
+                        piece.push(a.lerp(b, t0));
+                    }
+                    piece.push(a.lerp(b, t1));
+                    if t1 < 1. {
+                        pieces.push(std::mem::take(&mut piece));
+                    }
+                }
+                None => pieces.push(std::mem::take(&mut piece)),
+            }
+        }
+        pieces.push(piece);
+        pieces
+            .into_iter()
+            .filter(|piece| piece.len() > 1)
+            .map(|vertices| Self { vertices })
+            .collect()
+    }
+}
+
+/// Returns the range of `t` for which `a.lerp(b, t)` lies within the bounds, by Liang-Barsky
+/// clipping, or none if the segment misses them.
+fn clip_segment(a: P2, b: P2, bounds: Bounds) -> Option<(f32, f32)> {
+    let d = b - a;
+    let (mut t0, mut t1) = (0f32, 1f32);
+    for (p, q) in &[
+        (-d.x, a.x - bounds.min_x()),
+        (d.x, bounds.max_x() - a.x),
+        (-d.y, a.y - bounds.min_y()),
+        (d.y, bounds.max_y() - a.y),
+    ] {
+        if *p == 0. {
+            if *q < 0. {
+                return None;
+            }
+        } else if *p < 0. {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    if t0 <= t1 {
+        Some((t0, t1))
+    } else {
+        None
+    }
+}
+
+impl Paint for Polyline {
+    fn paint(&self, canvas: &mut Canvas) {
+        canvas.paint(FlatIterPath::new(self.vertices(), /*closed=*/ false))
+    }
+}
+
+impl Paint for &Polyline {
+    fn paint(&self, canvas: &mut Canvas) {
+        (**self).paint(canvas);
+    }
+}
+
+impl Subdivide for Polyline {
+    fn subdivide(self) -> Self {
+        let last = self.vertices().last();
+        self.vertices()
+            .tuple_windows()
+            .flat_map(|(a, b)| ArrayVec::from([a, a.lerp(b, 0.5)]).into_iter())
+            .chain(last)
+            .collect()
+    }
+}
+
+impl Rotate for Polyline {
+    fn rotate(mut self, pivot: P2, theta: Angle) -> Self {
+        self.vertices_mut().for_each(|v| {
+            *v = v.rotate(pivot, theta);
+        });
+
+        self
+    }
+}
+
+impl Translate for Polyline {
+    fn translate(mut self, translation: V2) -> Self {
+        self.vertices.iter_mut().for_each(|p| *p += translation);
+        self
+    }
+}
+
+impl<I> From<I> for Polyline
+where
+    I: Iterator<Item = P2>,
+{
+    fn from(src: I) -> Self {
+        Self {
+            vertices: src.collect(),
+        }
+    }
+}
+
+impl FromIterator<P2> for Polyline {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = P2>,
+    {
+        Self {
+            vertices: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn polyline(points: &[(f32, f32)]) -> Polyline {
+        points.iter().map(|&(x, y)| P2::new(x, y)).collect()
+    }
+
+    #[test]
+    fn polyline_length() {
+        let polyline = polyline(&[(0., 0.), (3., 4.), (3., 10.)]);
+        assert_eq!(polyline.length(), 11.);
+        assert_eq!(Polyline::from_iter(vec![P2::new(1., 1.)]).length(), 0.);
+    }
+
+    #[test]
+    fn polyline_samples_at_distance() {
+        let polyline = polyline(&[(0., 0.), (10., 0.), (10., 5.)]);
+        for i in 0..=10 {
+            let d = i as f32;
+            assert_eq!(polyline.sample_at_distance(d), Some(P2::new(d, 0.)));
+        }
+        assert_eq!(polyline.sample_at_distance(12.5), Some(P2::new(10., 2.5)));
+        assert_eq!(polyline.sample_at_distance(15.), Some(P2::new(10., 5.)));
+        assert_eq!(polyline.sample_at_distance(15.1), None);
+        assert_eq!(polyline.sample_at_distance(-0.1), None);
+    }
+
+    #[test]
+    fn polyline_reverses() {
+        let polyline = polyline(&[(0., 0.), (10., 0.), (10., 5.)]);
+        let reversed = polyline.clone().reversed();
+        assert_eq!(reversed.length(), polyline.length());
+        for i in 0..=15 {
+            let d = i as f32;
+            let (a, b) = (
+                reversed
+                    .sample_at_distance(d)
+                    .expect("a point on the reversed polyline"),
+                polyline
+                    .sample_at_distance(polyline.length() - d)
+                    .expect("a point on the polyline"),
+            );
+            assert!((a - b).length() < 0.0001, "{:?} != {:?}", a, b);
+        }
+        assert_eq!(reversed.reversed(), polyline);
+    }
+
+    #[test]
+    fn polyline_splits() {
+        let polyline = polyline(&[(0., 0.), (10., 0.), (10., 5.)]);
+        let vertices = |p: &Polyline| p.vertices().collect::<Vec<_>>();
+
+        let (first, second) = polyline.split_at(4.);
+        assert_eq!(vertices(&first), vec![P2::new(0., 0.), P2::new(4., 0.)]);
+        assert_eq!(
+            vertices(&second),
+            vec![P2::new(4., 0.), P2::new(10., 0.), P2::new(10., 5.)]
+        );
+
+        let (first, second) = polyline.split_at(12.);
+        assert_eq!(first.vertices().last(), second.vertices().next());
+        assert_eq!(first.length() + second.length(), polyline.length());
+
+        let (first, second) = polyline.split_at(0.);
+        assert_eq!(vertices(&first), vec![P2::new(0., 0.), P2::new(0., 0.)]);
+        assert_eq!(second.length(), polyline.length());
+
+        let (first, second) = polyline.split_at(20.);
+        assert_eq!(first, polyline);
+        assert_eq!(vertices(&second), vec![P2::new(10., 5.)]);
+    }
+
+    #[test]
+    fn polyline_clips() {
+        let bounds = Bounds::new(P2::new(0., 0.), crate::S2::new(10., 10.));
+        let vertices = |pieces: Vec<Polyline>| {
+            pieces
+                .iter()
+                .map(|p| p.vertices().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
+        let crossing = Polyline::from_iter(vec![P2::new(5., 5.), P2::new(15., 5.)]);
+        assert_eq!(
+            vertices(crossing.clip(bounds)),
+            vec![vec![P2::new(5., 5.), P2::new(10., 5.)]]
+        );
+
+        let inside = Polyline::from_iter(vec![P2::new(1., 1.), P2::new(9., 1.), P2::new(9., 9.)]);
+        assert_eq!(inside.clip(bounds), vec![inside.clone()]);
+
+        let outside = Polyline::from_iter(vec![P2::new(11., 1.), P2::new(20., 9.)]);
+        assert!(outside.clip(bounds).is_empty());
+
+        let weaving = Polyline::from_iter(vec![
+            P2::new(-5., 2.),
+            P2::new(5., 2.),
+            P2::new(5., 15.),
+            P2::new(8., 15.),
+            P2::new(8., 5.),
+        ]);
+        assert_eq!(
+            vertices(weaving.clip(bounds)),
+            vec![
+                vec![P2::new(0., 2.), P2::new(5., 2.), P2::new(5., 10.)],
+                vec![P2::new(8., 10.), P2::new(8., 5.)],
+            ]
+        );
+    }
+}
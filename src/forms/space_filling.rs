@@ -0,0 +1,127 @@
+//! Space filling curves.
+
+use crate::{Bounds, Polyline, P2};
+
+/// Returns a Hilbert curve of the given order filling the bounds.
+///
+/// The curve visits the center of each cell in a 2^order by 2^order grid, so it has 4^order
+/// vertices.
+pub fn hilbert_curve(order: u32, bounds: Bounds) -> Polyline {
+    let n = 1u64 << order;
+    (0..n * n)
+        .map(|d| {
+            let (x, y) = hilbert_cell(n, d);
+            cell_center(bounds, n, x, y)
+        })
+        .collect()
+}
+
+/// Returns a Peano curve of the given order filling the bounds.
+///
+/// The curve visits the center of each cell in a 3^order by 3^order grid, so it has 9^order
+/// vertices.
+pub fn peano_curve(order: u32, bounds: Bounds) -> Polyline {
+    let n = 3u64.pow(order);
+    (0..n * n)
+        .map(|d| {
+            let (x, y) = peano_cell(order, d);
+            cell_center(bounds, n, x, y)
+        })
+        .collect()
+}
+
+fn cell_center(bounds: Bounds, n: u64, x: u64, y: u64) -> P2 {
+    let cell_width = bounds.size.width / n as f32;
+    let cell_height = bounds.size.height / n as f32;
+    P2::new(
+        bounds.origin.x + (x as f32 + 0.5) * cell_width,
+        bounds.origin.y + (y as f32 + 0.5) * cell_height,
+    )
+}
+
+/// Maps a distance along a Hilbert curve on an n by n grid to its cell.
+fn hilbert_cell(n: u64, d: u64) -> (u64, u64) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+/// Maps a distance along a Peano curve of the given order to its cell, using Peano's original
+/// ternary digit construction.
+fn peano_cell(order: u32, d: u64) -> (u64, u64) {
+    let digits = (0..order * 2)
+        .rev()
+        .map(|i| (d / 3u64.pow(i)) % 3)
+        .collect::<Vec<_>>();
+    let flip = |digit: u64, parity: u64| if parity % 2 == 1 { 2 - digit } else { digit };
+
+    let (mut x, mut y) = (0, 0);
+    let (mut x_digit_sum, mut y_digit_sum) = (0, 0);
+    for pair in digits.chunks(2) {
+        let (a, b) = (pair[0], pair[1]);
+        x = x * 3 + flip(a, y_digit_sum);
+        x_digit_sum += a;
+        y = y * 3 + flip(b, x_digit_sum);
+        y_digit_sum += b;
+    }
+    (x, y)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::S2;
+    use itertools::Itertools;
+
+    fn bounds() -> Bounds {
+        Bounds::new(P2::new(10., 20.), S2::new(90., 90.))
+    }
+
+    fn assert_fills(curve: Polyline, vertex_count: usize, cell_size: f32) {
+        assert_eq!(curve.vertices().count(), vertex_count);
+        assert!(curve.vertices().all(|p| bounds().contains(p)));
+        assert_eq!(
+            curve
+                .vertices()
+                .unique_by(|p| (p.x as i32, p.y as i32))
+                .count(),
+            vertex_count
+        );
+        assert!(curve
+            .vertices()
+            .tuple_windows()
+            .all(|(a, b)| ((b - a).length() - cell_size).abs() < 0.001));
+    }
+
+    #[test]
+    fn hilbert() {
+        for order in 0..5 {
+            let n = 2usize.pow(order);
+            assert_fills(hilbert_curve(order, bounds()), n * n, 90. / n as f32);
+        }
+    }
+
+    #[test]
+    fn peano() {
+        for order in 0..4 {
+            let n = 3usize.pow(order);
+            assert_fills(peano_curve(order, bounds()), n * n, 90. / n as f32);
+        }
+    }
+}
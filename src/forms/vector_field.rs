@@ -0,0 +1,66 @@
+//! Vector field visualization.
+
+use crate::{Angle, Bounds, Polyline, Rotate, P2, PI, V2};
+use itertools::iproduct;
+
+/// Returns one arrow per cell of a `columns` by `rows` grid over the bounds, showing the field.
+///
+/// Each arrow starts at its cell center and points along the field there, with a length of the
+/// field's magnitude times `scale`. Arrows are single polylines which retrace the tip to draw
+/// both barbs of the head, so they should be stroked rather than filled.
+pub fn vector_field_arrows(
+    field: impl Fn(P2) -> V2,
+    bounds: Bounds,
+    columns: usize,
+    rows: usize,
+    scale: f32,
+) -> Vec<Polyline> {
+    let cell_width = bounds.size.width / columns as f32;
+    let cell_height = bounds.size.height / rows as f32;
+    iproduct!(0..rows, 0..columns)
+        .map(|(j, i)| {
+            let tail = P2::new(
+                bounds.origin.x + (i as f32 + 0.5) * cell_width,
+                bounds.origin.y + (j as f32 + 0.5) * cell_height,
+            );
+            arrow(tail, field(tail) * scale)
+        })
+        .collect()
+}
+
+fn arrow(tail: P2, v: V2) -> Polyline {
+    const BARB_ANGLE: f32 = PI * 5. / 6.;
+
+    let tip = tail + v;
+    let barb = tip + v / 4.;
+    vec![
+        tail,
+        tip,
+        barb.rotate(tip, Angle::radians(BARB_ANGLE)),
+        tip,
+        barb.rotate(tip, Angle::radians(-BARB_ANGLE)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::S2;
+
+    #[test]
+    fn arrows_follow_field() {
+        let bounds = Bounds::new(P2::new(0., 0.), S2::new(100., 50.));
+        let swirl = |p: P2| V2::new(-(p.y - 25.), p.x - 50.) / 10.;
+        let arrows = vector_field_arrows(swirl, bounds, 10, 5, 2.);
+
+        assert_eq!(arrows.len(), 50);
+        for arrow in arrows {
+            let points = arrow.vertices().collect::<Vec<_>>();
+            let (tail, tip) = (points[0], points[1]);
+            assert!(bounds.contains(tail));
+            assert!(((tip - tail) - swirl(tail) * 2.).length() < 0.0001);
+        }
+    }
+}
@@ -1,10 +1,13 @@
 //! GPU handle and types.
 
 use crate::{
-    raster::{raster_path, Method},
+    canvas::Disk,
+    raster::{raster_path_with_options, Method, RasterOptions},
     uniforms::*,
     Result,
 };
+use arrayvec::ArrayVec;
+use failure::format_err;
 use glium::{
     backend::{
         glutin::{headless::Headless, Display},
@@ -12,7 +15,8 @@ use glium::{
     },
     glutin::EventsLoop,
     implement_vertex,
-    index::PrimitiveType,
+    index::{NoIndices, PrimitiveType},
+    program::ProgramCreationInput,
     texture::{
         texture2d::Texture2d, texture2d_multisample::Texture2dMultisample, MipmapsOption,
         RawImage2d, UncompressedFloatFormat,
@@ -22,24 +26,45 @@ use glium::{
     Surface, VertexBuffer,
 };
 use glutin::dpi::PhysicalSize;
+use image::RgbaImage;
 use itertools::Itertools;
 use lyon_path::Builder;
-use palette::LinSrgba;
+use palette::{
+    encoding::{srgb::Srgb, TransferFn},
+    Component, LinSrgba,
+};
 use rand::random;
-use std::rc::Rc;
+use rayon::prelude::*;
+use std::{path::Path, rc::Rc};
 
+/// A vertex of rastered geometry, as uploaded to the GPU.
 #[derive(Debug, Copy, Clone)]
 pub struct GpuVertex {
     pub vpos: [f32; 2],
     pub vcol: [f32; 4],
+    /// Data for custom shaders, which the default vertex shader passes on to fragment shaders as
+    /// `v_data`. See `Canvas::set_vertex_data()`.
+    pub vdata: [f32; 4],
+}
+
+implement_vertex!(GpuVertex, vpos, vcol, vdata);
+
+/// A vertex of a disk drawn as a point sprite.
+#[derive(Debug, Copy, Clone)]
+struct DiskVertex {
+    vpos: [f32; 2],
+    vradius: f32,
+    vcol: [f32; 4],
 }
 
-implement_vertex!(GpuVertex, vpos, vcol);
+implement_vertex!(DiskVertex, vpos, vradius, vcol);
 
 pub(crate) const TEXTURE_FORMAT: UncompressedFloatFormat = UncompressedFloatFormat::F32F32F32F32;
 
 pub const VERTEX_SHADER: &str = include_str!("shaders/default.vert");
 const FRAGMENT_SHADER: &str = include_str!("shaders/default.frag");
+const DISK_VERTEX_SHADER: &str = include_str!("shaders/disk.vert");
+const DISK_FRAGMENT_SHADER: &str = include_str!("shaders/disk.frag");
 
 struct NoUniforms;
 
@@ -94,12 +119,82 @@ pub struct Shader {
     uniforms: UniformBuffer,
 }
 
+/// The geometry of an element.
+pub enum Geometry {
+    /// A path which is rastered into triangles with the given method and color.
+    Path {
+        path: Builder,
+        color: LinSrgba,
+        raster_method: Method,
+        raster_options: RasterOptions,
+        vertex_data: [f32; 4],
+    },
+    /// Disks which are drawn directly as antialiased point sprites, without tessellation.
+    Disks(Vec<Disk>),
+}
+
 /// A rasterable element in a composition.
 pub struct Element {
-    pub path: Builder,
-    pub color: LinSrgba,
-    pub raster_method: Method,
+    pub geometry: Geometry,
     pub shader: Shader,
+    pub blend_mode: BlendMode,
+    pub layer: usize,
+    /// Elements with a lower z order are drawn first. See `Canvas::set_z_order()`.
+    pub z_order: i32,
+    /// Multiplies the alpha of the element's colors. See `Canvas::set_opacity()`.
+    pub opacity: f32,
+}
+
+/// How the colors of painted elements combine with the colors already under them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    /// Paints over what is under by the element's alpha.
+    #[default]
+    Alpha,
+    /// Adds the element's color, scaled by its alpha, to what is under, which brightens overlaps
+    /// for glows and light.
+    Add,
+    /// Multiplies what is under by the element's color, which darkens as overlapping inks do.
+    /// The element's alpha does not fade the color.
+    Multiply,
+    /// Multiplies the inverses of what is under and the element's color, which lightens as
+    /// overlapping projections do. The element's alpha does not fade the color.
+    Screen,
+    /// Replaces what is under with the element's color and alpha, without blending.
+    Replace,
+}
+
+impl From<BlendMode> for Blend {
+    fn from(mode: BlendMode) -> Self {
+        let (source, destination) = match mode {
+            BlendMode::Replace => return Blend::default(),
+            BlendMode::Alpha => (
+                LinearBlendingFactor::SourceAlpha,
+                LinearBlendingFactor::OneMinusSourceAlpha,
+            ),
+            BlendMode::Add => (LinearBlendingFactor::SourceAlpha, LinearBlendingFactor::One),
+            BlendMode::Multiply => (
+                LinearBlendingFactor::Zero,
+                LinearBlendingFactor::SourceColor,
+            ),
+            BlendMode::Screen => (
+                LinearBlendingFactor::One,
+                LinearBlendingFactor::OneMinusSourceColor,
+            ),
+        };
+        Blend {
+            color: BlendingFunction::Addition {
+                source,
+                destination,
+            },
+            // Coverage accumulates the same way in every mode.
+            alpha: BlendingFunction::Addition {
+                source: LinearBlendingFactor::One,
+                destination: LinearBlendingFactor::OneMinusSourceAlpha,
+            },
+            constant_value: (0.0, 0.0, 0.0, 0.0),
+        }
+    }
 }
 
 pub struct DisplayFacade(Display);
@@ -114,6 +209,8 @@ pub trait FacadeExt: Facade {
     fn get_frame(&self) -> Option<Frame> {
         None
     }
+
+    fn set_title(&self, _title: &str) {}
 }
 
 impl FacadeExt for Headless {}
@@ -122,6 +219,10 @@ impl FacadeExt for DisplayFacade {
     fn get_frame(&self) -> Option<Frame> {
         Some(self.0.draw())
     }
+
+    fn set_title(&self, title: &str) {
+        self.0.gl_window().window().set_title(title);
+    }
 }
 
 /// A handle to the GPU for managing buffers and shaders.
@@ -129,6 +230,7 @@ impl FacadeExt for DisplayFacade {
 pub struct Gpu {
     pub(crate) ctx: Rc<dyn FacadeExt>,
     program: Rc<Program>,
+    disk_program: Rc<Program>,
     height_sign: f32,
 }
 
@@ -138,6 +240,7 @@ struct GpuCommand<'a, S> {
     pub target: &'a mut S,
     pub program: &'a Program,
     pub uniforms: &'a UniformBuffer,
+    pub blend_mode: BlendMode,
 }
 
 impl Gpu {
@@ -160,10 +263,12 @@ impl Gpu {
             FRAGMENT_SHADER,
             None,
         )?);
+        let disk_program = Rc::new(compile_disk_program(ctx.as_ref())?);
 
         Ok((
             Gpu {
                 program,
+                disk_program,
                 ctx,
                 height_sign: 1.,
             },
@@ -178,7 +283,7 @@ impl Gpu {
                 width: width as f64,
                 height: height as f64,
             })
-            .with_title("Hello world");
+            .with_title("valora");
         let cb = glium::glutin::ContextBuilder::new()
             .with_srgb(false)
             .with_multisampling(8);
@@ -192,10 +297,12 @@ impl Gpu {
             FRAGMENT_SHADER,
             None,
         )?);
+        let disk_program = Rc::new(compile_disk_program(ctx.as_ref())?);
 
         Ok((
             Gpu {
                 program,
+                disk_program,
                 ctx,
                 height_sign: -1.,
             },
@@ -208,6 +315,16 @@ impl Gpu {
         self.ctx.get_frame()
     }
 
+    /// Returns the dimensions of the window's framebuffer, in physical pixels.
+    pub(crate) fn framebuffer_dimensions(&self) -> (u32, u32) {
+        self.ctx.get_context().get_framebuffer_dimensions()
+    }
+
+    /// Sets the title of the window, if there is one.
+    pub(crate) fn set_title(&self, title: &str) {
+        self.ctx.set_title(title);
+    }
+
     pub(crate) fn default_shader(&self) -> Shader {
         Shader {
             id: random(),
@@ -237,18 +354,75 @@ impl Gpu {
         }
     }
 
-    pub fn build_texture(&self, width: u32, height: u32) -> Result<Texture2dMultisample> {
+    /// Builds a texture to render into with the given number of samples per pixel.
+    pub fn build_texture(
+        &self,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Result<Texture2dMultisample> {
+        if samples == 0 {
+            return Err(format_err!(
+                "Render textures need at least 1 sample per pixel"
+            ));
+        }
         Ok(Texture2dMultisample::empty_with_format(
             self.ctx.as_ref(),
             TEXTURE_FORMAT,
             MipmapsOption::NoMipmap,
             width,
             height,
-            /*samples=*/ 16,
+            samples,
+        )?)
+    }
+
+    /// Uploads an image to a texture for shaders to sample, keeping its alpha.
+    ///
+    /// Colors are decoded from sRGB, so shaders sample the same linear colors they paint with.
+    /// The first row of the image is at the top of the texture, where the `t` texture coordinate
+    /// is 1.
+    ///
+    /// With `mipmaps`, the texture also keeps successively halved copies of the image, which
+    /// take a third more memory but keep it from aliasing when drawn much smaller than it is.
+    /// Sample it with a `SampledTexture` with linear filtering to blend between them.
+    pub fn build_image_texture(&self, image: &RgbaImage, mipmaps: bool) -> Result<Texture2d> {
+        let decode = |c: u8| <Srgb as TransferFn>::into_linear(c.convert::<f32>());
+        let data = image
+            .pixels()
+            .flat_map(|pixel| {
+                let [red, green, blue, alpha] = pixel.0;
+                ArrayVec::from([
+                    decode(red),
+                    decode(green),
+                    decode(blue),
+                    alpha.convert::<f32>(),
+                ])
+                .into_iter()
+            })
+            .collect::<Vec<f32>>();
+        Ok(Texture2d::with_format(
+            self.ctx.as_ref(),
+            RawImage2d::from_raw_rgba_reversed(&data, image.dimensions()),
+            TEXTURE_FORMAT,
+            if mipmaps {
+                MipmapsOption::AutoGeneratedMipmaps
+            } else {
+                MipmapsOption::NoMipmap
+            },
         )?)
     }
 
-    fn build_ram_texture(&self, width: u32, height: u32) -> Result<Texture2d> {
+    /// Opens an image file, such as a PNG or JPEG, and uploads it to a texture for shaders to
+    /// sample as with `build_image_texture()`.
+    pub fn load_image_texture(&self, path: impl AsRef<Path>, mipmaps: bool) -> Result<Texture2d> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .map_err(|e| format_err!("Failed to load texture {:?}: {}", path, e))?
+            .to_rgba();
+        self.build_image_texture(&image, mipmaps)
+    }
+
+    pub(crate) fn build_ram_texture(&self, width: u32, height: u32) -> Result<Texture2d> {
         Ok(Texture2d::empty_with_format(
             self.ctx.as_ref(),
             TEXTURE_FORMAT,
@@ -280,6 +454,12 @@ impl Gpu {
         Ok(target.read())
     }
 
+    /// Reads a rendered texture back into an image, with its colors encoded as sRGB as in saved
+    /// frames.
+    pub fn read_image(&self, texture: &Texture2dMultisample) -> Result<RgbaImage> {
+        Ok(encode_image(self.read_to_ram(texture)?))
+    }
+
     pub fn render(
         &self,
         width: u32,
@@ -288,7 +468,9 @@ impl Gpu {
         target: &mut impl Surface,
     ) -> Result<()> {
         let elements = elements.into_iter();
-        for (_id, batch) in &elements.group_by(|e| e.shader.id) {
+        for ((_id, blend_mode, disks), batch) in
+            &elements.group_by(|e| (e.shader.id, e.blend_mode, e.geometry.is_disks()))
+        {
             let mut batch = batch.peekable();
             let mut first = if let Some(first) = batch.peek() {
                 first.shader.clone()
@@ -297,31 +479,47 @@ impl Gpu {
                 continue;
             };
 
-            // TODO: reconcile conflicts between user uniforms and the defaults
-            first.uniforms.push(
-                String::from("_valora_width"),
-                UniformValue::Float(width as f32),
-            );
-            first.uniforms.push(
-                String::from("_valora_height"),
-                UniformValue::Float(height as f32),
-            );
-            first.uniforms.push(
-                String::from("_valora_height_sign"),
-                UniformValue::Float(self.height_sign),
-            );
-
-            let (_, cpu_vertices, cpu_indices) = batch
-                .try_fold::<_, _, Result<(u32, Vec<GpuVertex>, Vec<u32>)>>(
-                    (0, vec![], vec![]),
-                    |(idx, mut vertices, mut indices), element| {
-                        let (mut new_vertices, new_indices) =
-                            raster_path(element.path, element.raster_method, element.color)?;
-                        vertices.append(&mut new_vertices);
-                        indices.extend(new_indices.into_iter().map(|i| i + idx));
-                        Ok((vertices.len() as u32, vertices, indices))
-                    },
+            if disks {
+                let cpu_vertices = batch
+                    .flat_map(|element| {
+                        let opacity = element.opacity;
+                        let disks = match element.geometry {
+                            Geometry::Disks(disks) => disks,
+                            Geometry::Path { .. } => vec![],
+                        };
+                        disks.into_iter().map(move |disk| (disk, opacity))
+                    })
+                    .map(|(disk, opacity)| DiskVertex {
+                        vpos: [disk.center.x, disk.center.y],
+                        vradius: disk.radius,
+                        vcol: [
+                            disk.color.color.red,
+                            disk.color.color.green,
+                            disk.color.color.blue,
+                            disk.color.alpha * opacity,
+                        ],
+                    })
+                    .collect::<Vec<_>>();
+                // Disks are drawn by the built-in disk program, not the element's shader, so they
+                // take only the frame's uniforms.
+                let mut uniforms = UniformBuffer::default();
+                push_frame_uniforms(&mut uniforms, width, height, self.height_sign);
+                let vertices = VertexBuffer::new(self.ctx.as_ref(), cpu_vertices.as_slice())?;
+                target.draw(
+                    &vertices,
+                    NoIndices(PrimitiveType::Points),
+                    self.disk_program.as_ref(),
+                    &uniforms,
+                    &draw_parameters(blend_mode),
                 )?;
+                continue;
+            }
+
+            // TODO: reconcile conflicts between user uniforms and the defaults
+            push_frame_uniforms(&mut first.uniforms, width, height, self.height_sign);
+
+            let (cpu_vertices, cpu_indices) =
+                batch_paths(batch.map(|element| (element.geometry, element.opacity)))?;
 
             let vertices = VertexBuffer::new(self.ctx.as_ref(), cpu_vertices.as_slice())?;
             let indices = IndexBuffer::new(
@@ -336,6 +534,7 @@ impl Gpu {
                 target,
                 program: first.program.as_ref(),
                 uniforms: &first.uniforms,
+                blend_mode,
             })?;
         }
 
@@ -348,24 +547,221 @@ impl Gpu {
             &cmd.indices,
             cmd.program,
             cmd.uniforms,
-            &DrawParameters {
-                blend: Blend {
-                    color: BlendingFunction::Addition {
-                        source: LinearBlendingFactor::SourceAlpha,
-                        destination: LinearBlendingFactor::OneMinusSourceAlpha,
-                    },
-                    alpha: BlendingFunction::Addition {
-                        source: LinearBlendingFactor::One,
-                        destination: LinearBlendingFactor::OneMinusSourceAlpha,
-                    },
-                    constant_value: (0.0, 0.0, 0.0, 0.0),
-                },
-                line_width: Some(1.0),
-                multisampling: true,
-                dithering: false,
-                smooth: Some(glium::draw_parameters::Smooth::Nicest),
-                ..Default::default()
-            },
+            &draw_parameters(cmd.blend_mode),
         )?)
     }
 }
+
+impl Geometry {
+    fn is_disks(&self) -> bool {
+        match self {
+            Geometry::Disks(_) => true,
+            Geometry::Path { .. } => false,
+        }
+    }
+}
+
+/// Encodes the linear colors of pixels read from the GPU as sRGB.
+pub(crate) fn encode_image(raw: RawImage2d<u8>) -> RgbaImage {
+    let (width, height) = (raw.width, raw.height);
+    let mut pixels = raw.data.into_owned();
+    pixels.par_chunks_mut(4).for_each(|pixel| {
+        // Alpha is linear coverage, so only the color channels are encoded.
+        for v in &mut pixel[..3] {
+            *v = <Srgb as TransferFn>::from_linear(v.convert::<f32>()).convert::<u8>();
+        }
+    });
+    RgbaImage::from_raw(width, height, pixels).expect("Pixels read from the GPU to fill the image")
+}
+
+fn compile_disk_program(facade: &impl Facade) -> Result<Program> {
+    Ok(Program::new(
+        facade,
+        ProgramCreationInput::SourceCode {
+            vertex_shader: DISK_VERTEX_SHADER,
+            tessellation_control_shader: None,
+            tessellation_evaluation_shader: None,
+            geometry_shader: None,
+            fragment_shader: DISK_FRAGMENT_SHADER,
+            transform_feedback_varyings: None,
+            outputs_srgb: false,
+            uses_point_size: true,
+        },
+    )?)
+}
+
+fn draw_parameters<'a>(blend_mode: BlendMode) -> DrawParameters<'a> {
+    DrawParameters {
+        blend: blend_mode.into(),
+        line_width: Some(1.0),
+        multisampling: true,
+        dithering: false,
+        smooth: Some(glium::draw_parameters::Smooth::Nicest),
+        ..Default::default()
+    }
+}
+
+/// Rasters a batch of paths with their opacities into one set of vertices and indices, giving each
+/// vertex its path's vertex data. Disks are skipped; they are drawn without rastering.
+fn batch_paths(
+    geometries: impl Iterator<Item = (Geometry, f32)>,
+) -> Result<(Vec<GpuVertex>, Vec<u32>)> {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    for (geometry, opacity) in geometries {
+        if let Geometry::Path {
+            path,
+            color,
+            raster_method,
+            raster_options,
+            vertex_data,
+        } = geometry
+        {
+            let (mut new_vertices, new_indices) =
+                raster_path_with_options(path, raster_method, color, raster_options)?;
+            new_vertices.iter_mut().for_each(|v| {
+                v.vdata = vertex_data;
+                v.vcol[3] *= opacity;
+            });
+            let offset = vertices.len() as u32;
+            indices.extend(new_indices.into_iter().map(|i| i + offset));
+            vertices.append(&mut new_vertices);
+        }
+    }
+    Ok((vertices, indices))
+}
+
+/// Pushes the uniforms valora gives every draw: the frame's dimensions and the sign of its y axis.
+fn push_frame_uniforms(uniforms: &mut UniformBuffer, width: u32, height: u32, height_sign: f32) {
+    uniforms.push(
+        String::from("_valora_width"),
+        UniformValue::Float(width as f32),
+    );
+    uniforms.push(
+        String::from("_valora_height"),
+        UniformValue::Float(height as f32),
+    );
+    uniforms.push(
+        String::from("_valora_height_sign"),
+        UniformValue::Float(height_sign),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::P2;
+
+    #[test]
+    fn blend_modes() {
+        let color = |mode: BlendMode| match Blend::from(mode).color {
+            BlendingFunction::Addition {
+                source,
+                destination,
+            } => (source, destination),
+            function => panic!("Unexpected blending function {:?}", function),
+        };
+        assert_eq!(
+            Blend::from(BlendMode::default()),
+            draw_parameters(BlendMode::Alpha).blend
+        );
+        assert_eq!(
+            color(BlendMode::Alpha),
+            (
+                LinearBlendingFactor::SourceAlpha,
+                LinearBlendingFactor::OneMinusSourceAlpha
+            )
+        );
+        assert_eq!(
+            color(BlendMode::Add),
+            (LinearBlendingFactor::SourceAlpha, LinearBlendingFactor::One)
+        );
+        assert_eq!(
+            color(BlendMode::Multiply),
+            (
+                LinearBlendingFactor::Zero,
+                LinearBlendingFactor::SourceColor
+            )
+        );
+        assert_eq!(
+            color(BlendMode::Screen),
+            (
+                LinearBlendingFactor::One,
+                LinearBlendingFactor::OneMinusSourceColor
+            )
+        );
+        for &mode in &[
+            BlendMode::Alpha,
+            BlendMode::Add,
+            BlendMode::Multiply,
+            BlendMode::Screen,
+        ] {
+            assert_eq!(Blend::from(mode).alpha, Blend::from(BlendMode::Alpha).alpha);
+        }
+        assert_eq!(Blend::from(BlendMode::Replace), Blend::default());
+    }
+
+    #[test]
+    fn batched_paths_carry_their_vertex_data() {
+        let path = |x: f32, vertex_data: [f32; 4]| {
+            let mut path = Builder::new();
+            path.move_to(P2::new(x, 0.));
+            path.line_to(P2::new(x + 1., 0.));
+            path.line_to(P2::new(x + 1., 1.));
+            path.close();
+            Geometry::Path {
+                path,
+                color: LinSrgba::new(1., 1., 1., 0.8),
+                raster_method: Method::Fill,
+                raster_options: RasterOptions::default(),
+                vertex_data,
+            }
+        };
+        let (vertices, indices) = batch_paths(
+            vec![
+                (path(0., [1., 2., 3., 4.]), 0.5),
+                (Geometry::Disks(vec![]), 1.),
+                (path(10., [5., 6., 7., 8.]), 1.),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(indices.len(), 6);
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+        for (i, v) in vertices.iter().enumerate() {
+            let (vdata, alpha) = if v.vpos[0] < 5. {
+                ([1., 2., 3., 4.], 0.4)
+            } else {
+                ([5., 6., 7., 8.], 0.8)
+            };
+            assert_eq!(v.vdata, vdata, "{}", i);
+            assert!((v.vcol[3] - alpha).abs() < 0.0001, "{}", i);
+        }
+        // The second path's triangles index its own vertices.
+        assert!(indices[3..]
+            .iter()
+            .all(|&i| vertices[i as usize].vpos[0] >= 10.));
+    }
+
+    #[test]
+    fn frame_uniforms() {
+        let mut uniforms = UniformBuffer::default();
+        push_frame_uniforms(&mut uniforms, 640, 480, -1.);
+
+        let mut values = vec![];
+        uniforms.visit_values(|name, value| match value {
+            UniformValue::Float(v) => values.push((name.to_string(), v)),
+            _ => panic!("Unexpected uniform {}", name),
+        });
+        assert_eq!(
+            values,
+            vec![
+                (String::from("_valora_width"), 640.),
+                (String::from("_valora_height"), 480.),
+                (String::from("_valora_height_sign"), -1.),
+            ]
+        );
+    }
+}
@@ -2,13 +2,14 @@
 
 mod gpu;
 mod noise_traits;
-mod raster;
 mod render;
+mod video;
 
 pub mod canvas;
 pub mod forms;
 pub mod paint;
 pub mod path;
+pub mod raster;
 pub mod shaders;
 pub mod transforms;
 pub mod uniforms;
@@ -16,7 +17,7 @@ pub mod uniforms;
 /// Exhuastive set of imports for painting.
 pub mod prelude {
     pub use self::{
-        canvas::*, forms::*, paint::*, path::*, shaders::*, transforms::*, uniforms::*,
+        canvas::*, forms::*, paint::*, path::*, raster::*, shaders::*, transforms::*, uniforms::*,
     };
     pub use super::*;
     pub use euclid::{self, Rect};
@@ -28,14 +29,15 @@ pub mod prelude {
         self, encoding::Srgb, Alpha, Blend, ComponentWise, Hue, IntoColor, LinSrgb, LinSrgba,
         Saturate, *,
     };
-    pub use rand::{self, rngs::StdRng, Rng, SeedableRng};
+    pub use rand::{self, rngs::StdRng, Rng, RngCore, SeedableRng};
+    pub use rand_pcg::Pcg64;
     pub use structopt::StructOpt;
 
     pub use std::f32::consts::PI;
 }
 
 pub use self::{
-    gpu::{Gpu, Shader},
+    gpu::{BlendMode, Gpu, GpuVertex, Shader},
     render::Context,
     shaders::ShaderProgram,
 };
@@ -43,9 +45,19 @@ pub use self::{
 use self::{gpu::*, prelude::*, raster::Method};
 use euclid::{Point3D, Size2D, UnknownUnit, Vector2D, Vector3D};
 use failure::Error;
+use image::RgbaImage;
+use itertools::Itertools;
 use lyon_path::math::Point;
 use render::*;
-use std::{path::PathBuf, time::Duration};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use video::{FrameEncoder, Gif, ImageWriter, Video};
 
 /// A two dimensional point.
 pub type P2 = Point;
@@ -62,6 +74,9 @@ pub type V3 = Vector3D<f32, UnknownUnit>;
 /// A two dimensional size.
 pub type S2 = Size2D<f32, UnknownUnit>;
 
+/// A two dimensional axis aligned rectangle.
+pub type Bounds = Rect<f32, UnknownUnit>;
+
 /// An angle.
 pub type Angle = euclid::Angle<f32>;
 
@@ -80,6 +95,13 @@ pub struct Options {
     #[structopt(flatten)]
     pub world: World,
 
+    /// A phrase to derive the RNG seed from, such as the title of the piece, so it is easier to
+    /// share and remember than a number.
+    ///
+    /// A nonzero numeric seed takes precedence over the phrase.
+    #[structopt(long = "seed_phrase")]
+    pub seed_phrase: Option<String>,
+
     /// In brainstorm mode:
     ///
     ///   * When rendering a limited number of frames to screen, the preview will not close.
@@ -94,9 +116,277 @@ pub struct Options {
     #[structopt(short = "d", long = "delay", default_value = "0")]
     pub delay: usize,
 
+    /// Preview frames as fast as they render instead of at the frame rate.
+    ///
+    /// Frame times still advance by one frame rate period per frame.
+    #[structopt(long = "uncapped")]
+    pub uncapped: bool,
+
+    /// Log how long each frame took to paint, draw and output, and summarize the times on exit.
+    ///
+    /// Output is presenting to the window, or reading back and queuing saved frames.
+    #[structopt(long = "profile")]
+    pub profile: bool,
+
+    /// The frame at which to start rendering.
+    ///
+    /// Earlier frames are still painted, but not rendered, so that artists which keep state
+    /// between frames reach the start frame in the same state as if every frame had been shown.
+    #[structopt(long = "start_frame", default_value = "0")]
+    pub start_frame: usize,
+
     /// Prefix of output path. Output is <prefix>/<seed>/<frame_number>.png
+    ///
+    /// Each seed directory also gets an options.txt which `Options::from_file` can load to
+    /// reproduce its frames.
     #[structopt(short = "o", long = "output", parse(from_os_str))]
     pub output: Option<PathBuf>,
+
+    /// Path of a video to encode the saved frames into with ffmpeg, instead of saving them as
+    /// images. The format follows from the extension, e.g. "out.mp4".
+    #[structopt(long = "video", parse(from_os_str))]
+    pub video: Option<PathBuf>,
+
+    /// Path of a looping GIF to encode the saved frames into, instead of saving them as images.
+    /// It plays at the frame rate.
+    #[structopt(long = "gif", parse(from_os_str), conflicts_with = "video")]
+    pub gif: Option<PathBuf>,
+
+    /// The layers to render, separated by commas, or all layers if none are given. See
+    /// `Canvas::set_layer()`.
+    ///
+    /// Rendering one layer at a time exports layers separately, such as for print separations.
+    #[structopt(long = "layers", use_delimiter = true)]
+    pub layers: Vec<usize>,
+
+    /// The filter used to resolve the multisampled render into saved frames: "box" or "tent".
+    #[structopt(long = "resolve", default_value = "box")]
+    pub resolve: Resolve,
+
+    /// The number of samples per pixel to render with, which smooths the edges of shapes. More
+    /// samples take more memory and time; 1 renders with aliased edges.
+    #[structopt(long = "samples", default_value = "16")]
+    pub samples: u32,
+
+    /// What to clear each frame to before painting it: "transparent", or an sRGB hex color such
+    /// as "#ffffff" or "#00000080". Without one, frames are not cleared, so each paints over the
+    /// last. Saved images keep transparency.
+    #[structopt(long = "background")]
+    pub background: Option<Background>,
+
+    /// The scale at which pressing 'c' in the preview captures the previewed frame, saving it to
+    /// <output>/<seed>/capture-<frame_number>.png.
+    #[structopt(long = "capture_scale", default_value = "4")]
+    pub capture_scale: f32,
+}
+
+impl Options {
+    /// Loads options from a file written by `Options::export`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let args = std::fs::read_to_string(path)?;
+        Ok(Self::from_iter_safe(
+            std::iter::once("valora").chain(args.lines()),
+        )?)
+    }
+
+    /// Returns the seed for the run: the numeric seed if it is nonzero or there is no seed phrase,
+    /// and otherwise a hash of the seed phrase.
+    ///
+    /// A seed of 0 counts as unset, since it is the default when no seed is given.
+    pub fn seed(&self) -> u64 {
+        match &self.seed_phrase {
+            Some(phrase) if self.world.seed == 0 => seed_from_phrase(phrase),
+            _ => self.world.seed,
+        }
+    }
+
+    /// Returns these options pinned to the given seed of the run, for reproducing it alone.
+    fn for_seed(&self, seed: u64) -> Self {
+        Self {
+            world: World { seed, ..self.world },
+            seed_phrase: None,
+            brainstorm: false,
+            ..self.clone()
+        }
+    }
+
+    /// Writes the options to a file that `Options::from_file` can load, to reproduce this run.
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_args().join("\n"))?;
+        Ok(())
+    }
+
+    /// Returns the command line arguments which parse back into these options.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = self.world.to_args();
+        if let Some(phrase) = &self.seed_phrase {
+            args.extend(vec![String::from("--seed_phrase"), phrase.clone()]);
+        }
+        if self.brainstorm {
+            args.push(String::from("--brainstorm"));
+        }
+        args.extend(vec![String::from("--delay"), self.delay.to_string()]);
+        if self.uncapped {
+            args.push(String::from("--uncapped"));
+        }
+        if self.profile {
+            args.push(String::from("--profile"));
+        }
+        args.extend(vec![
+            String::from("--start_frame"),
+            self.start_frame.to_string(),
+        ]);
+        if let Some(video) = &self.video {
+            args.extend(vec![
+                String::from("--video"),
+                video.to_string_lossy().into_owned(),
+            ]);
+        }
+        if let Some(gif) = &self.gif {
+            args.extend(vec![
+                String::from("--gif"),
+                gif.to_string_lossy().into_owned(),
+            ]);
+        }
+        if !self.layers.is_empty() {
+            args.extend(vec![
+                String::from("--layers"),
+                self.layers.iter().map(usize::to_string).join(","),
+            ]);
+        }
+        args.extend(vec![String::from("--resolve"), self.resolve.to_string()]);
+        args.extend(vec![String::from("--samples"), self.samples.to_string()]);
+        if let Some(background) = &self.background {
+            args.extend(vec![String::from("--background"), background.to_string()]);
+        }
+        args.extend(vec![
+            String::from("--capture_scale"),
+            self.capture_scale.to_string(),
+        ]);
+        if let Some(output) = &self.output {
+            args.extend(vec![
+                String::from("--output"),
+                output.to_string_lossy().into_owned(),
+            ]);
+        }
+        args
+    }
+}
+
+/// Hashes a phrase into a seed which is the same on every machine, with 64 bit FNV-1a.
+pub fn seed_from_phrase(phrase: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    phrase.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// A filter for resolving multisampled renders into single samples per pixel when saving to file.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Resolve {
+    /// Averages the samples within each pixel.
+    Box,
+    /// Weights the samples of each pixel and its neighbors with a tent filter. This smooths
+    /// stair-stepping on high contrast edges at the cost of slightly softening the image.
+    Tent,
+}
+
+impl FromStr for Resolve {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "box" => Ok(Resolve::Box),
+            "tent" => Ok(Resolve::Tent),
+            _ => Err(failure::format_err!(
+                "Unknown resolve filter {:?}; expected \"box\" or \"tent\"",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Resolve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Resolve::Box => write!(f, "box"),
+            Resolve::Tent => write!(f, "tent"),
+        }
+    }
+}
+
+/// What frames are cleared to before they are painted.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Background {
+    /// Clears to transparent black, so unpainted areas are saved with zero alpha.
+    Transparent,
+    /// Clears to a color.
+    Solid(LinSrgba),
+}
+
+impl Background {
+    /// Clears the surface to the background.
+    pub(crate) fn clear(&self, surface: &mut impl glium::Surface) {
+        match self {
+            Background::Transparent => surface.clear_color(0., 0., 0., 0.),
+            Background::Solid(color) => surface.clear_color(
+                color.color.red,
+                color.color.green,
+                color.color.blue,
+                color.alpha,
+            ),
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "transparent" {
+            return Ok(Background::Transparent);
+        }
+        let error = || {
+            failure::format_err!(
+                "Unknown background {:?}; expected \"transparent\" or a hex color like \"#ffffff\"",
+                s
+            )
+        };
+        let hex = s.trim_start_matches('#');
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return Err(error());
+        }
+        let channel =
+            |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| error());
+        let alpha = if hex.len() == 8 { channel(3)? } else { 255 };
+        let color = palette::Srgb::new(channel(0)?, channel(1)?, channel(2)?)
+            .into_format::<f32>()
+            .into_linear();
+        Ok(Background::Solid(Alpha {
+            color,
+            alpha: alpha.convert::<f32>(),
+        }))
+    }
+}
+
+impl fmt::Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Background::Transparent => write!(f, "transparent"),
+            Background::Solid(color) => {
+                let srgb = palette::Srgb::from_linear(color.color).into_format::<u8>();
+                write!(
+                    f,
+                    "#{:02x}{:02x}{:02x}{:02x}",
+                    srgb.red,
+                    srgb.green,
+                    srgb.blue,
+                    color.alpha.convert::<u8>()
+                )
+            }
+        }
+    }
 }
 
 /// The world in which the painting takes place.
@@ -104,6 +394,8 @@ pub struct Options {
 #[structopt(name = "world")]
 pub struct World {
     /// The RNG seed for this painting.
+    ///
+    /// A seed of 0, the default, counts as unset: with a seed phrase, the phrase gives the seed.
     #[structopt(short = "e", long = "seed", default_value = "0")]
     pub seed: u64,
 
@@ -150,6 +442,23 @@ impl World {
     pub fn center(&self) -> P2 {
         P2::new(self.width / 2.0, self.height / 2.0)
     }
+
+    /// Returns the command line arguments which parse back into this world.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec![
+            ("--seed", self.seed.to_string()),
+            ("--width", self.width.to_string()),
+            ("--height", self.height.to_string()),
+            ("--scale", self.scale.to_string()),
+            ("--frames_per_second", self.framerate.to_string()),
+        ];
+        if let Some(frames) = self.frames {
+            args.push(("--frames", frames.to_string()));
+        }
+        args.into_iter()
+            .flat_map(|(flag, value)| vec![String::from(flag), value])
+            .collect()
+    }
 }
 
 /// Draws a rectangle path covering the entire canvas.
@@ -169,16 +478,202 @@ pub trait Artist: Sized {
     ///
     /// This would be a place to compile any GLSL or construct any expensive
     /// resources needed across the whole composition.
-    fn setup(gpu: Gpu, world: World, rng: &mut StdRng) -> Result<Self>;
+    fn setup(gpu: Gpu, world: World, rng: &mut dyn RngCore) -> Result<Self>;
 
     /// Paints a single frame.
     fn paint(&mut self, ctx: Context, canvas: &mut Canvas);
 }
 
+/// An artist which paints artist `A` and then artist `B` over it on the same canvas.
+///
+/// Each layer gets its own rng, seeded from the composition rng during setup, so the randomness
+/// of one layer does not depend on how much the other consumes. Nest `Layered` to stack more than
+/// two artists.
+pub struct Layered<A, B> {
+    bottom: A,
+    bottom_rng: Pcg64,
+    top: B,
+    top_rng: Pcg64,
+}
+
+impl<A: Artist, B: Artist> Artist for Layered<A, B> {
+    fn setup(gpu: Gpu, world: World, rng: &mut dyn RngCore) -> Result<Self> {
+        let mut bottom_rng = Pcg64::seed_from_u64(rng.gen());
+        let mut top_rng = Pcg64::seed_from_u64(rng.gen());
+        Ok(Self {
+            bottom: A::setup(gpu.clone(), world, &mut bottom_rng)?,
+            bottom_rng,
+            top: B::setup(gpu, world, &mut top_rng)?,
+            top_rng,
+        })
+    }
+
+    fn paint(&mut self, ctx: Context, canvas: &mut Canvas) {
+        self.paint_layers(ctx, canvas, A::paint, B::paint);
+    }
+}
+
+impl<A, B> Layered<A, B> {
+    /// Paints the bottom layer and then the top, each given its own rng in the context.
+    fn paint_layers<C: ?Sized>(
+        &mut self,
+        ctx: Context,
+        canvas: &mut C,
+        paint_bottom: impl FnOnce(&mut A, Context, &mut C),
+        paint_top: impl FnOnce(&mut B, Context, &mut C),
+    ) {
+        paint_bottom(
+            &mut self.bottom,
+            Context {
+                rng: &mut self.bottom_rng,
+                ..ctx
+            },
+            canvas,
+        );
+        paint_top(
+            &mut self.top,
+            Context {
+                rng: &mut self.top_rng,
+                ..ctx
+            },
+            canvas,
+        );
+    }
+}
+
+/// Returns where to save a frame: a directory for the seed under the base path, holding images
+/// named by frame number, zero padded to `number_width` digits so they sort in order.
+fn frame_path(base_path: &Path, seed: u64, frame_number: usize, number_width: usize) -> PathBuf {
+    base_path.join(seed.to_string()).join(format!(
+        "{number:>0width$}.png",
+        number = frame_number,
+        width = number_width
+    ))
+}
+
+/// Renders one frame of an artist headlessly and returns its pixels, without opening a window or
+/// saving anything, such as to test a painting against a known image or to paint inside another
+/// program.
+///
+/// The artist is set up fresh in the options' world with the given seed and painted from the
+/// first frame through the given one, since artists may keep state between frames. The frame is
+/// rendered with the options' samples, background and layers, resolved with the box filter, and its
+/// colors are encoded as in saved frames. Without a background, it starts from transparent.
+pub fn render_frame<A: Artist>(options: &Options, seed: u64, frame: usize) -> Result<RgbaImage> {
+    render_frame_fn(options, seed, frame, |gpu, world, rng| {
+        let mut artist = A::setup(gpu, world, rng)?;
+        Ok(move |ctx: Context, canvas: &mut Canvas| artist.paint(ctx, canvas))
+    })
+}
+
+/// Renders one frame of an artist defined by raw functions headlessly. See `render_frame`.
+pub fn render_frame_fn<F>(
+    options: &Options,
+    seed: u64,
+    frame: usize,
+    f: impl Fn(Gpu, World, &mut dyn RngCore) -> Result<F>,
+) -> Result<RgbaImage>
+where
+    F: FnMut(Context, &mut Canvas),
+{
+    let (gpu, _) = Gpu::new()?;
+    let world = World {
+        seed,
+        ..options.world
+    };
+    render_image(options, &gpu, &f, world, frame)
+}
+
+/// Paints a fresh artist from the first frame through the given one at the capture scale, and
+/// saves the last frame.
+fn capture_frame<F>(
+    options: &Options,
+    gpu: &Gpu,
+    f: &impl Fn(Gpu, World, &mut dyn RngCore) -> Result<F>,
+    seed: u64,
+    frame: usize,
+) -> Result<PathBuf>
+where
+    F: FnMut(Context, &mut Canvas),
+{
+    let world = World {
+        seed,
+        scale: options.capture_scale,
+        ..options.world
+    };
+    let image = render_image(options, gpu, f, world, frame)?;
+
+    let path = options
+        .output
+        .clone()
+        .unwrap_or_default()
+        .join(seed.to_string())
+        .join(format!("capture-{}.png", frame));
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    image.save(&path)?;
+    Ok(path)
+}
+
+/// Paints a fresh artist in the world from the first frame through the given one, and returns
+/// the last frame rendered. Replaying is needed because artists may keep state between frames.
+fn render_image<F>(
+    options: &Options,
+    gpu: &Gpu,
+    f: &impl Fn(Gpu, World, &mut dyn RngCore) -> Result<F>,
+    world: World,
+    frame: usize,
+) -> Result<RgbaImage>
+where
+    F: FnMut(Context, &mut Canvas),
+{
+    let (width, height) = (
+        (world.width * world.scale) as u32,
+        (world.height * world.scale) as u32,
+    );
+    let mut rng = Pcg64::seed_from_u64(world.seed);
+    let mut paint_fn = f(gpu.clone(), world, &mut rng)?;
+    let mut paint = |i: usize| {
+        let mut canvas = Canvas::new(gpu.default_shader(), world.scale);
+        paint_fn(
+            Context {
+                rng: &mut rng,
+                world,
+                options,
+                frame: i,
+                time: Duration::from_secs_f32(i as f32 / world.framerate as f32),
+                render_times: &[],
+            },
+            &mut canvas,
+        );
+        canvas
+    };
+    (0..frame).for_each(|i| drop(paint(i)));
+    let canvas = paint(frame);
+
+    let layers = &options.layers;
+    let elements = canvas
+        .into_iter()
+        .filter(|element| layers.is_empty() || layers.contains(&element.layer));
+    // A new buffer holds whatever memory it was given, so it is cleared even without a
+    // background for the frame to come out the same every time.
+    let buffer = gpu.build_texture(width, height, options.samples)?;
+    options
+        .background
+        .unwrap_or(Background::Transparent)
+        .clear(&mut buffer.as_surface());
+    gpu.render(width, height, elements, &mut buffer.as_surface())?;
+    gpu.read_image(&buffer)
+}
+
 /// Run an artist defined by raw functions.
 ///
 /// Takes a function that produces the function that should paint each frame.
-pub fn run_fn<F>(options: Options, f: impl Fn(Gpu, World, &mut StdRng) -> Result<F>) -> Result<()>
+pub fn run_fn<F>(
+    options: Options,
+    f: impl Fn(Gpu, World, &mut dyn RngCore) -> Result<F>,
+) -> Result<()>
 where
     F: FnMut(Context, &mut Canvas),
 {
@@ -195,58 +690,107 @@ where
         .chars()
         .count();
 
-    let (gpu, mut strategy) = if let Some(base_path) = options.output.clone() {
-        let (gpu, _) = Gpu::new()?;
-        let buffer = gpu.build_texture(output_width, output_height)?;
-
-        (
-            gpu,
-            RenderStrategy::File {
-                buffer,
-                output_path: move |frame_number: usize, seed: u64| {
-                    let mut base_path = base_path.clone();
-                    base_path.push(format!("{}", seed));
-                    std::fs::create_dir_all(&base_path)
-                        .expect(&format!("To create save directory {:?}", base_path));
-                    base_path.push(format!(
-                        "{number:>0width$}.png",
-                        number = frame_number,
-                        width = number_width
-                    ));
-                    base_path
+    let (gpu, mut strategy) =
+        if options.output.is_some() || options.video.is_some() || options.gif.is_some() {
+            let base_path = options.output.clone().unwrap_or_default();
+            let run_options = options.clone();
+            let described_seeds = RefCell::new(HashSet::new());
+            let (gpu, _) = Gpu::new()?;
+            let buffer = gpu.build_texture(output_width, output_height, options.samples)?;
+            let (width, height, framerate) = (output_width, output_height, options.world.framerate);
+            let encoder = match (&options.video, &options.gif) {
+                (Some(path), _) => Some(FrameEncoder::Video(Video::new(
+                    path, width, height, framerate,
+                )?)),
+                (None, Some(path)) => {
+                    Some(FrameEncoder::Gif(Gif::new(path, width, height, framerate)?))
+                }
+                (None, None) => Some(FrameEncoder::Images(ImageWriter::new())),
+            };
+            let resolve_program = match options.resolve {
+                Resolve::Box => None,
+                Resolve::Tent => Some(gpu.compile_glsl(include_str!("shaders/resolve_tent.frag"))?),
+            };
+
+            (
+                gpu,
+                RenderStrategy::File {
+                    buffer,
+                    resolve_program,
+                    encoder,
+                    output_path: move |frame_number: usize, seed: u64| -> Result<PathBuf> {
+                        let path = frame_path(&base_path, seed, frame_number, number_width);
+                        let directory = path.parent().unwrap_or(&base_path);
+                        if !described_seeds.borrow().contains(&seed) {
+                            std::fs::create_dir_all(directory).map_err(|e| {
+                                failure::format_err!(
+                                    "Failed to create save directory {:?}: {}",
+                                    directory,
+                                    e
+                                )
+                            })?;
+                            run_options
+                                .for_seed(seed)
+                                .export(directory.join("options.txt"))
+                                .map_err(|e| {
+                                    failure::format_err!(
+                                        "Failed to write options into {:?}: {}",
+                                        directory,
+                                        e
+                                    )
+                                })?;
+                            described_seeds.borrow_mut().insert(seed);
+                        }
+                        Ok(path)
+                    },
                 },
-            },
-        )
-    } else {
-        let (gpu, events_loop, (screen_width, screen_height)) =
-            Gpu::with_window(output_width, output_height)?;
-        let buffer = gpu.build_texture(screen_width, screen_height)?;
-
-        let wait = Duration::from_secs_f64(1. / options.world.framerate as f64);
-        let gpu_clone = gpu.clone();
-
-        let texture_glsl = include_str!("shaders/texture.frag");
-        let texture_program = gpu.compile_glsl(texture_glsl)?;
-
-        (
-            gpu,
-            RenderStrategy::Screen {
-                events_loop,
-                wait,
-                buffer,
-                texture_program,
-                get_frame: move || {
-                    gpu_clone
-                        .get_frame()
-                        .expect("To get frame from windowed gpu")
+            )
+        } else {
+            let (gpu, events_loop, (screen_width, screen_height)) =
+                Gpu::with_window(output_width, output_height)?;
+            let buffer = gpu.build_texture(screen_width, screen_height, options.samples)?;
+
+            let wait = if options.uncapped {
+                None
+            } else {
+                Some(Duration::from_secs_f64(1. / options.world.framerate as f64))
+            };
+            let gpu_clone = gpu.clone();
+
+            let texture_glsl = include_str!("shaders/texture.frag");
+            let texture_program = gpu.compile_glsl(texture_glsl)?;
+
+            (
+                gpu,
+                RenderStrategy::Screen {
+                    events_loop,
+                    wait,
+                    buffer,
+                    texture_program,
+                    get_frame: move || {
+                        gpu_clone
+                            .get_frame()
+                            .expect("To get frame from windowed gpu")
+                    },
                 },
-            },
-        )
-    };
+            )
+        };
 
-    let mut current_seed = options.world.seed;
+    match &options.seed_phrase {
+        Some(phrase) if options.world.seed != 0 => eprintln!(
+            "Both seed {} and seed phrase {:?} are set; using the seed.",
+            options.world.seed, phrase
+        ),
+        _ => {}
+    }
+    let mut seed_history = SeedHistory::new(options.seed());
+    eprintln!("Seed: {}", seed_history);
+    let mut start_frame = options.start_frame;
+    let mut paused = false;
+    let mut profile = Profile::default();
     loop {
-        let mut rng = StdRng::seed_from_u64(current_seed);
+        let current_seed = seed_history.current();
+        let mut rng = Pcg64::seed_from_u64(current_seed);
         let mut paint_fn = f(gpu.clone(), options.world, &mut rng)?;
 
         let mut renderer = Renderer {
@@ -257,26 +801,57 @@ where
                     seed: current_seed,
                     ..options.world
                 },
+                start_frame,
                 ..options.clone()
             },
             rng: &mut rng,
             output_width: output_width,
             output_height: output_height,
+            paused,
+            seed_history: &seed_history,
+            profile: &mut profile,
         };
 
         let report = renderer.render_frames(|ctx, canvas| paint_fn(ctx, canvas))?;
 
         if let Some(rebuild) = report.rebuild {
+            start_frame = options.start_frame;
+            paused = false;
             match rebuild {
-                Rebuild::NewSeed(new_seed) => {
-                    current_seed = new_seed;
+                Rebuild::NewSeed(new_seed) => seed_history.push(new_seed),
+                Rebuild::SameSeed => {}
+                Rebuild::Seek(frame) => {
+                    start_frame = frame;
+                    paused = true;
                 }
+                Rebuild::Capture(frame) => {
+                    match capture_frame(&options, &gpu, &f, current_seed, frame) {
+                        Ok(path) => eprintln!("Captured frame {} to {:?}", frame, path),
+                        Err(e) => eprintln!("Failed to capture frame {}: {}", frame, e),
+                    }
+                    start_frame = frame;
+                    paused = true;
+                }
+                Rebuild::PreviousSeed => seed_history.back(),
+                Rebuild::NextSeed => seed_history.forward(),
+            }
+            if seed_history.current() != current_seed {
+                eprintln!("Seed: {}", seed_history);
             }
         } else if report.explicit_quit || !options.brainstorm {
             break;
         }
     }
 
+    if let RenderStrategy::File { encoder, .. } = &mut strategy {
+        if let Some(encoder) = encoder.take() {
+            encoder.finish()?;
+        }
+    }
+    if options.profile {
+        eprintln!("{}", profile);
+    }
+
     Ok(())
 }
 
@@ -287,3 +862,249 @@ pub fn run<A: Artist>(options: Options) -> Result<()> {
         Ok(move |ctx: Context, canvas: &mut Canvas| artist.paint(ctx, canvas))
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seed_phrases() {
+        assert_eq!(seed_from_phrase(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(seed_from_phrase("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(seed_from_phrase("foobar"), 0x8594_4171_f739_67e8);
+        assert_ne!(
+            seed_from_phrase("foggy-harbor"),
+            seed_from_phrase("foggy-harbour")
+        );
+
+        let seed = |args: &[&str]| {
+            Options::from_iter(std::iter::once("valora").chain(args.iter().copied())).seed()
+        };
+        assert_eq!(seed(&[]), 0);
+        assert_eq!(seed(&["--seed", "5"]), 5);
+        assert_eq!(
+            seed(&["--seed_phrase", "foobar"]),
+            seed_from_phrase("foobar")
+        );
+        assert_eq!(seed(&["--seed", "5", "--seed_phrase", "foobar"]), 5);
+        // A seed of 0 is the default, so it counts as unset.
+        assert_eq!(
+            seed(&["--seed", "0", "--seed_phrase", "foobar"]),
+            seed_from_phrase("foobar")
+        );
+    }
+
+    #[test]
+    fn layers_paint_in_order_with_their_own_rngs() {
+        let options = Options::from_iter(vec!["valora", "--width", "10", "--height", "10"]);
+        // Each layer logs its draws from its rng in place of painting elements.
+        fn layer(
+            (name, draws): &mut (&'static str, usize),
+            ctx: Context,
+            log: &mut Vec<(&'static str, u64)>,
+        ) {
+            (0..*draws).for_each(|_| log.push((name, ctx.rng.next_u64())));
+        }
+        let paint = |draws: usize| {
+            let mut rng = Pcg64::seed_from_u64(0);
+            let mut layered = Layered {
+                bottom: ("bottom", draws),
+                bottom_rng: Pcg64::seed_from_u64(1),
+                top: ("top", 1),
+                top_rng: Pcg64::seed_from_u64(2),
+            };
+            let mut log = vec![];
+            let ctx = Context {
+                rng: &mut rng,
+                world: options.world,
+                options: &options,
+                frame: 0,
+                time: Duration::from_secs(0),
+                render_times: &[],
+            };
+            layered.paint_layers(ctx, &mut log, layer, layer);
+            log
+        };
+
+        let few = paint(1);
+        let many = paint(5);
+        let names =
+            |log: &[(&'static str, u64)]| log.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names(&few), vec!["bottom", "top"]);
+        assert_eq!(
+            names(&many),
+            vec!["bottom", "bottom", "bottom", "bottom", "bottom", "top"]
+        );
+        // The top layer draws the same however much the bottom layer consumed.
+        assert_eq!(few.last(), many.last());
+        assert_eq!(few[0], many[0]);
+        assert_ne!(few[0].1, few[1].1);
+    }
+
+    #[test]
+    fn seeded_rng_is_stable() {
+        // Paintings must reproduce from their seed, so these may never change.
+        let mut rng = Pcg64::seed_from_u64(1234);
+        let values = (0..4).map(|_| rng.gen::<u64>()).collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                6302795928263238970,
+                5761065944030956297,
+                9135137209376192308,
+                2388750056048661623
+            ]
+        );
+    }
+
+    #[test]
+    fn frame_paths() {
+        let path = frame_path(Path::new("out"), 12345, 7, 3);
+        assert_eq!(path, Path::new("out").join("12345").join("007.png"));
+        assert!(!path.to_string_lossy().contains(char::is_whitespace));
+
+        let path = frame_path(Path::new(""), u64::MAX, 120, 1);
+        assert_eq!(path, Path::new("18446744073709551615").join("120.png"));
+    }
+
+    #[test]
+    fn options_for_seed() {
+        let options = Options::from_iter_safe(vec![
+            "valora",
+            "--seed_phrase",
+            "foggy harbor",
+            "--brainstorm",
+            "--frames",
+            "10",
+        ])
+        .expect("to parse options");
+        let pinned = Options::from_iter_safe(
+            std::iter::once(String::from("valora")).chain(options.for_seed(42).to_args()),
+        )
+        .expect("to parse pinned options");
+
+        assert_eq!(pinned.seed(), 42);
+        assert!(!pinned.brainstorm);
+        assert_eq!(pinned.world.frames, Some(10));
+    }
+
+    #[test]
+    fn backgrounds() {
+        for text in &["transparent", "#ffffffff", "#00000080", "#1a2b3cff"] {
+            let background = text.parse::<Background>().expect("to parse background");
+            assert_eq!(background.to_string(), *text);
+        }
+        assert_eq!(
+            "#ff8000".parse::<Background>().unwrap().to_string(),
+            "#ff8000ff"
+        );
+        match "ffffff".parse::<Background>().unwrap() {
+            Background::Solid(color) => {
+                assert_eq!(color.color, LinSrgb::new(1., 1., 1.));
+                assert_eq!(color.alpha, 1.);
+            }
+            Background::Transparent => panic!("Expected a solid background"),
+        }
+        for text in &["", "white", "#fff", "#fffffg", "#ffffffffff", "#ééé"] {
+            assert!(text.parse::<Background>().is_err(), "{:?} parsed", text);
+        }
+    }
+
+    #[test]
+    fn options_round_trip_through_file() {
+        let options = Options {
+            world: World {
+                seed: 1234,
+                width: 300.,
+                height: 200.5,
+                scale: 2.,
+                frames: Some(48),
+                framerate: 30,
+            },
+            seed_phrase: Some(String::from("foggy harbor")),
+            brainstorm: true,
+            delay: 3,
+            uncapped: true,
+            profile: true,
+            start_frame: 7,
+            output: Some(PathBuf::from("out/dir")),
+            video: Some(PathBuf::from("out.mp4")),
+            gif: None,
+            layers: vec![0, 2],
+            resolve: Resolve::Tent,
+            samples: 4,
+            background: Some(Background::Transparent),
+            capture_scale: 2.5,
+        };
+        let path = std::env::temp_dir().join("valora-options-round-trip");
+        options.export(&path).expect("to export options");
+        let loaded = Options::from_file(&path).expect("to load options");
+        std::fs::remove_file(&path).expect("to remove options file");
+
+        assert_eq!(loaded.to_args(), options.to_args());
+        assert_eq!(loaded.world.seed, 1234);
+        assert_eq!(loaded.world.height, 200.5);
+        assert_eq!(loaded.world.frames, Some(48));
+        assert_eq!(loaded.output, options.output);
+        assert_eq!(loaded.video, options.video);
+        assert_eq!(loaded.resolve, Resolve::Tent);
+        assert_eq!(loaded.samples, 4);
+        assert_eq!(loaded.capture_scale, 2.5);
+        assert_eq!(loaded.background, Some(Background::Transparent));
+        assert_eq!(loaded.start_frame, 7);
+        assert!(loaded.uncapped);
+        assert!(loaded.profile);
+        assert_eq!(loaded.layers, vec![0, 2]);
+        assert_eq!(loaded.seed_phrase, options.seed_phrase);
+    }
+
+    #[test]
+    fn exported_config_keeps_run_options() {
+        let options = Options::from_iter(vec![
+            "valora",
+            "--seed_phrase",
+            "foggy harbor",
+            "--samples",
+            "4",
+            "--background",
+            "#ffffff",
+            "--layers",
+            "1",
+            "--resolve",
+            "tent",
+            "--capture_scale",
+            "2",
+        ]);
+        let world = World {
+            seed: 99,
+            ..options.world
+        };
+        let mut rng = Pcg64::seed_from_u64(0);
+        let ctx = Context {
+            rng: &mut rng,
+            world,
+            options: &options,
+            frame: 12,
+            time: Duration::from_secs(0),
+            render_times: &[],
+        };
+        let path = std::env::temp_dir().join("valora-exported-config");
+        ctx.export_config(&path).expect("to export config");
+        let loaded = Options::from_file(&path).expect("to load config");
+        std::fs::remove_file(&path).expect("to remove config file");
+
+        let expected = Options {
+            world,
+            seed_phrase: None,
+            start_frame: 12,
+            ..options.clone()
+        };
+        assert_eq!(loaded.to_args(), expected.to_args());
+        assert_eq!(loaded.seed(), 99);
+        assert_eq!(loaded.samples, 4);
+        assert_eq!(loaded.layers, vec![1]);
+        assert_eq!(loaded.resolve, Resolve::Tent);
+        assert_eq!(loaded.background, options.background);
+        assert_eq!(loaded.start_frame, 12);
+    }
+}
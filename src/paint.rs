@@ -1,6 +1,6 @@
 //! Paint trait and implementations.
 
-use crate::canvas::Canvas;
+use crate::{canvas::Canvas, Angle};
 use lyon_path::PathEvent;
 
 /// A trait for types which can be represented on a `Canvas`.
@@ -33,6 +33,22 @@ impl<P: Paint> Paint for Stroked<P> {
     }
 }
 
+/// Paints a path with a hachure fill of parallel strokes.
+pub struct Hachured<D> {
+    pub element: D,
+    pub angle: Angle,
+    pub gap: f32,
+    pub width: f32,
+}
+
+impl<P: Paint> Paint for Hachured<P> {
+    fn paint(&self, comp: &mut Canvas) {
+        self.element.paint(comp);
+        comp.set_stroke_width(self.width);
+        comp.hachure(self.angle, self.gap);
+    }
+}
+
 impl<P> Paint for P
 where
     P: Iterator<Item = PathEvent> + Clone,
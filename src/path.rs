@@ -1,7 +1,9 @@
 //! Path types and tools.
 
-use crate::P2;
-use lyon_path::PathEvent;
+use crate::{Result, P2, V2};
+use failure::format_err;
+use lyon_path::{Builder, Path, PathEvent};
+use std::iter::Peekable;
 
 /// An adapter for iterators over points that implements `Path`.
 #[derive(Debug, Copy, Clone)]
@@ -58,3 +60,235 @@ where
         result
     }
 }
+
+/// Parses the `d` attribute of an SVG path element into a path.
+///
+/// Supports moveto, lineto, horizontal and vertical lineto, cubic and quadratic curves and
+/// closepath, each in absolute and relative forms, and repeated arguments after a command. Other
+/// commands, such as arcs, are an error. Coordinates are kept as they are, in SVG's y-down space.
+/// Paint the path with `canvas.paint(path.iter())`.
+pub fn svg_path(d: &str) -> Result<Path> {
+    let mut tokens = svg_tokens(d)?.into_iter().peekable();
+    let mut builder = Builder::new();
+    let (mut current, mut start) = (P2::zero(), P2::zero());
+    let (mut started, mut open) = (false, false);
+    while let Some(token) = tokens.next() {
+        let command = match token {
+            SvgToken::Command(command) => command,
+            SvgToken::Number(n) => {
+                return Err(format_err!(
+                    "Expected an SVG path command but found the number {}",
+                    n
+                ))
+            }
+        };
+        let relative = command.is_ascii_lowercase();
+        let command = command.to_ascii_uppercase();
+        if !started && command != 'M' {
+            return Err(format_err!(
+                "SVG paths must start with a moveto, not '{}'",
+                command
+            ));
+        }
+        let mut repeated = false;
+        loop {
+            let origin = if relative { current } else { P2::zero() };
+            if !open && command != 'M' && command != 'Z' {
+                // Drawing after a closepath starts a new subpath where the last began.
+                builder.move_to(current);
+                open = true;
+            }
+            match command {
+                'M' if !repeated => {
+                    current = svg_point(&mut tokens, command, origin)?;
+                    start = current;
+                    builder.move_to(current);
+                    started = true;
+                    open = true;
+                }
+                'Z' => {
+                    builder.close();
+                    current = start;
+                    open = false;
+                    break;
+                }
+                'M' | 'L' => {
+                    current = svg_point(&mut tokens, command, origin)?;
+                    builder.line_to(current);
+                }
+                'H' => {
+                    current.x = svg_number(&mut tokens, command)? + origin.x;
+                    builder.line_to(current);
+                }
+                'V' => {
+                    current.y = svg_number(&mut tokens, command)? + origin.y;
+                    builder.line_to(current);
+                }
+                'C' => {
+                    let ctrl1 = svg_point(&mut tokens, command, origin)?;
+                    let ctrl2 = svg_point(&mut tokens, command, origin)?;
+                    current = svg_point(&mut tokens, command, origin)?;
+                    builder.cubic_bezier_to(ctrl1, ctrl2, current);
+                }
+                'Q' => {
+                    let ctrl = svg_point(&mut tokens, command, origin)?;
+                    current = svg_point(&mut tokens, command, origin)?;
+                    builder.quadratic_bezier_to(ctrl, current);
+                }
+                _ => return Err(format_err!("Unsupported SVG path command '{}'", command)),
+            }
+            repeated = true;
+            if let Some(SvgToken::Number(_)) = tokens.peek() {
+                continue;
+            }
+            break;
+        }
+    }
+    Ok(builder.build())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum SvgToken {
+    Command(char),
+    Number(f32),
+}
+
+fn svg_tokens(d: &str) -> Result<Vec<SvgToken>> {
+    let chars = d.chars().collect::<Vec<_>>();
+    let digits = |mut i: usize| {
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    let sign = |i: usize| {
+        if i < chars.len() && (chars[i] == '-' || chars[i] == '+') {
+            i + 1
+        } else {
+            i
+        }
+    };
+
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' {
+            i += 1;
+        } else if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            tokens.push(SvgToken::Command(c));
+            i += 1;
+        } else {
+            let begin = i;
+            i = digits(sign(i));
+            if i < chars.len() && chars[i] == '.' {
+                i = digits(i + 1);
+            }
+            if i > begin && i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                i = digits(sign(i + 1));
+            }
+            let text = chars[begin..i].iter().collect::<String>();
+            let n = text.parse::<f32>().map_err(|_| match text.as_str() {
+                "" => format_err!("Unexpected {:?} at {} in SVG path", c, begin),
+                _ => format_err!("Invalid number {:?} at {} in SVG path", text, begin),
+            })?;
+            tokens.push(SvgToken::Number(n));
+            if i == begin {
+                i += 1;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn svg_number(tokens: &mut Peekable<impl Iterator<Item = SvgToken>>, command: char) -> Result<f32> {
+    match tokens.next() {
+        Some(SvgToken::Number(n)) => Ok(n),
+        _ => Err(format_err!(
+            "SVG path command '{}' is missing arguments",
+            command
+        )),
+    }
+}
+
+fn svg_point(
+    tokens: &mut Peekable<impl Iterator<Item = SvgToken>>,
+    command: char,
+    origin: P2,
+) -> Result<P2> {
+    let x = svg_number(tokens, command)?;
+    let y = svg_number(tokens, command)?;
+    Ok(origin + V2::new(x, y))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn svg_paths() {
+        let path = svg_path("M10,10 l20 0 C40 10 40 30 30 30 Z m5-5 h3 v3 q1 1 2 0 z").unwrap();
+        let p = P2::new;
+        assert_eq!(
+            path.iter().collect::<Vec<_>>(),
+            vec![
+                PathEvent::Begin { at: p(10., 10.) },
+                PathEvent::Line {
+                    from: p(10., 10.),
+                    to: p(30., 10.)
+                },
+                PathEvent::Cubic {
+                    from: p(30., 10.),
+                    ctrl1: p(40., 10.),
+                    ctrl2: p(40., 30.),
+                    to: p(30., 30.)
+                },
+                PathEvent::End {
+                    last: p(30., 30.),
+                    first: p(10., 10.),
+                    close: true
+                },
+                PathEvent::Begin { at: p(15., 5.) },
+                PathEvent::Line {
+                    from: p(15., 5.),
+                    to: p(18., 5.)
+                },
+                PathEvent::Line {
+                    from: p(18., 5.),
+                    to: p(18., 8.)
+                },
+                PathEvent::Quadratic {
+                    from: p(18., 8.),
+                    ctrl: p(19., 9.),
+                    to: p(20., 8.)
+                },
+                PathEvent::End {
+                    last: p(20., 8.),
+                    first: p(15., 5.),
+                    close: true
+                },
+            ]
+        );
+
+        // Arguments after a moveto repeat as linetos, and numbers may run together.
+        let path = svg_path("M0 0 1-1.5.5 1e1 10,0").unwrap();
+        let points = path
+            .iter()
+            .filter_map(|event| match event {
+                PathEvent::Line { to, .. } => Some(to),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(points, vec![p(1., -1.5), p(0.5, 10.), p(10., 0.)]);
+
+        for invalid in &[
+            "L 1 1",
+            "M 0 0 L 1",
+            "M 0 0 A 1 1 0 0 1 2 2",
+            "M 0 0 # 1",
+            "5",
+        ] {
+            assert!(svg_path(invalid).is_err(), "{:?} parsed", invalid);
+        }
+    }
+}
@@ -1,97 +1,1558 @@
 //! Path rasterization.
 
-use crate::{gpu::GpuVertex, Result, P2};
-use lyon_path::Builder;
+use crate::{gpu::GpuVertex, Angle, Bounds, Result, Rotate, P2, V2};
+use image::{ImageBuffer, Rgba};
+use lyon_path::{iterator::PathIterator, Builder, Path, PathEvent};
 use lyon_tessellation::{
-    BuffersBuilder, FillAttributes, FillOptions, FillTessellator, StrokeAttributes, StrokeOptions,
-    StrokeTessellator, VertexBuffers,
+    BuffersBuilder, FillAttributes, FillOptions, FillRule as LyonFillRule, FillTessellator,
+    LineCap, LineJoin, StrokeAttributes, StrokeOptions, StrokeTessellator, TessellationError,
+    VertexBuffers,
 };
-use palette::LinSrgba;
+use palette::{
+    encoding::{srgb::Srgb, TransferFn},
+    Component, LinSrgba, Mix,
+};
+use rayon::prelude::*;
+use std::fmt;
 
 /// The method by which the rasterizer will rasterize the vector path.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Method {
     /// In fill method, the rasterizer will treat all the area inside the path as part of the
     /// raster area. In this method, paths are automatically closed by assuming an edge from the
     /// last to the first vertex.
     Fill,
     /// In stroke method, the rasterizer will treat the area immediately adjacent the path within
-    /// the given width as part of the rastered area. In this method, paths are left open
+    /// the style's width as part of the rastered area. In this method, paths are left open
     /// and no edge between the last and first vertex is assumed.
-    Stroke(f32),
+    Stroke(StrokeStyle),
+    /// In hachure method, the rasterizer will fill the area inside the path with parallel
+    /// strokes of the given width, `gap` apart and rotated by `angle`, for a hand drawn look.
+    /// Paths are automatically closed as in fill method.
+    Hachure { angle: Angle, gap: f32, width: f32 },
+    /// In gradient stroke method, the rasterizer strokes the path as in stroke method, but
+    /// colors it by distance along the path: from the element's color at the start of the path to
+    /// `end` at its end. Distance is measured across all subpaths in turn, not within each.
+    GradientStroke { style: StrokeStyle, end: LinSrgba },
+    /// In fill and stroke method, the rasterizer fills the path with the element's color as in
+    /// fill method, and strokes it over the fill with the `stroke` color as in stroke method, in
+    /// one element.
+    FillAndStroke {
+        style: StrokeStyle,
+        stroke: LinSrgba,
+    },
+    /// In gradient fill method, the rasterizer fills the path as in fill method, but colors it
+    /// with a linear gradient instead of the element's color.
+    ///
+    /// Points are placed along the gradient by projecting them onto `direction` in the path's
+    /// bounds normalized to the unit square, so that `(1, 0)` runs from the left of the path to
+    /// its right and `(1, 1)` runs corner to corner. `stops` pairs positions along the gradient
+    /// with colors, in order of position, and points before the first or after the
+    /// last stop take its color. Stops out of order or at non-finite positions are an error. Colors are computed at the tessellated vertices and blended
+    /// across the triangles between them, so stops between the vertices of large triangles
+    /// are only approximated.
+    GradientFill {
+        stops: Vec<(f32, LinSrgba)>,
+        direction: V2,
+    },
+}
+
+/// The shape of a stroke.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle {
+    /// The width of the stroke.
+    pub width: f32,
+    /// The shape of the ends of open subpaths.
+    pub cap: Cap,
+    /// The shape of the corners between segments.
+    pub join: Join,
+    /// How far past a corner a miter join may reach, as a multiple of the stroke width, before
+    /// the corner is beveled instead. Must be at least 1.
+    pub miter_limit: f32,
+    /// Alternating lengths of dashes and gaps along each subpath, starting with a dash. An odd
+    /// number of lengths is repeated to make an even number, and no lengths means a solid
+    /// stroke.
+    pub dashes: Vec<f32>,
+    /// How far into the dash pattern each subpath starts.
+    pub dash_offset: f32,
+}
+
+impl StrokeStyle {
+    /// A stroke of the given width with butt caps and miter joins limited to 4 widths.
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            cap: Cap::Butt,
+            join: Join::Miter,
+            miter_limit: StrokeOptions::DEFAULT_MITER_LIMIT,
+            dashes: vec![],
+            dash_offset: 0.,
+        }
+    }
+
+    pub fn with_cap(self, cap: Cap) -> Self {
+        Self { cap, ..self }
+    }
+
+    pub fn with_join(self, join: Join) -> Self {
+        Self { join, ..self }
+    }
+
+    pub fn with_miter_limit(self, miter_limit: f32) -> Self {
+        Self {
+            miter_limit,
+            ..self
+        }
+    }
+
+    pub fn with_dashes(self, dashes: Vec<f32>, dash_offset: f32) -> Self {
+        Self {
+            dashes,
+            dash_offset,
+            ..self
+        }
+    }
 }
 
+/// The shape of the ends of a stroke.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cap {
+    /// The stroke ends flat at the end of the path.
+    Butt,
+    /// The stroke ends in a half disk around the end of the path.
+    Round,
+    /// The stroke ends flat, half its width past the end of the path.
+    Square,
+}
+
+/// The shape of the corners of a stroke.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Join {
+    /// The outer edges of the stroke extend until they meet in a sharp corner.
+    Miter,
+    /// The corner is rounded off with a disk around the vertex.
+    Round,
+    /// The corner is cut off straight across.
+    Bevel,
+}
+
+impl From<Join> for LineJoin {
+    fn from(join: Join) -> Self {
+        match join {
+            Join::Miter => LineJoin::Miter,
+            Join::Round => LineJoin::Round,
+            Join::Bevel => LineJoin::Bevel,
+        }
+    }
+}
+
+impl From<Cap> for LineCap {
+    fn from(cap: Cap) -> Self {
+        match cap {
+            Cap::Butt => LineCap::Butt,
+            Cap::Round => LineCap::Round,
+            Cap::Square => LineCap::Square,
+        }
+    }
+}
+
+/// The default maximum distance between a rastered path and its approximation by line segments.
+const TOLERANCE: f32 = 0.05;
+
+/// Options for rastering paths that apply to every method.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RasterOptions {
+    /// The maximum distance, in output pixels, between a rastered curve and the line segments
+    /// approximating it. Smaller tolerances give smoother curves from more vertices.
+    pub tolerance: f32,
+    /// Which regions of a path count as inside it when filling. Hachures always use the even-odd
+    /// rule.
+    pub fill_rule: FillRule,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: TOLERANCE,
+            fill_rule: FillRule::EvenOdd,
+        }
+    }
+}
+
+/// A rule for which regions of a path are inside it, by how the path winds around them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillRule {
+    /// Regions the path crosses around an odd number of times are inside, so overlaps of
+    /// subpaths make holes.
+    EvenOdd,
+    /// Regions the path winds around at all are inside, unless windings in opposite
+    /// directions cancel out.
+    NonZero,
+}
+
+impl From<FillRule> for LyonFillRule {
+    fn from(rule: FillRule) -> Self {
+        match rule {
+            FillRule::EvenOdd => LyonFillRule::EvenOdd,
+            FillRule::NonZero => LyonFillRule::NonZero,
+        }
+    }
+}
+
+/// The width, in output pixels, of strokes rastered with a width of zero.
+pub const HAIRLINE_WIDTH: f32 = 1.0;
+
+/// The most lines the hachure method draws across one path.
+pub const MAX_HACHURE_LINES: usize = 1_000_000;
+
+/// An error rastering a path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RasterError {
+    /// The stroke width was negative or not finite.
+    InvalidStrokeWidth(f32),
+    /// The gap between hachure lines was not positive and finite.
+    NonPositiveHachureGap(f32),
+    /// The gap between hachure lines was so small for the path that it would take more than
+    /// `MAX_HACHURE_LINES` lines to fill, or the path was not finite.
+    TooManyHachureLines(f32),
+    /// The tolerance was not positive and finite.
+    NonPositiveTolerance(f32),
+    /// A gradient had no color stops.
+    NoGradientStops,
+    /// A gradient's stop positions were not finite or decreased, listed here in order.
+    InvalidGradientStops(Vec<f32>),
+    /// A gradient's direction was not finite.
+    NonFiniteGradientDirection(V2),
+    /// The miter limit was less than 1 or not finite.
+    MiterLimitBelowOne(f32),
+    /// The dash pattern had a negative or non-finite length, or lengths adding up to zero.
+    InvalidDashes(Vec<f32>),
+    /// The tessellator could not triangulate the path, as can happen for degenerate
+    /// or non-finite geometry.
+    Tessellation(TessellationError),
+}
+
+impl fmt::Display for RasterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RasterError::InvalidStrokeWidth(width) => {
+                write!(
+                    f,
+                    "Stroke width must be finite and not negative; got {}",
+                    width
+                )
+            }
+            RasterError::NonPositiveHachureGap(gap) => {
+                write!(f, "Hachure gap must be positive and finite; got {}", gap)
+            }
+            RasterError::TooManyHachureLines(gap) => write!(
+                f,
+                "Hachure gap {} needs more than {} lines to fill the path",
+                gap, MAX_HACHURE_LINES
+            ),
+            RasterError::NonPositiveTolerance(tolerance) => {
+                write!(
+                    f,
+                    "Tolerance must be positive and finite; got {}",
+                    tolerance
+                )
+            }
+            RasterError::NoGradientStops => write!(f, "Gradients must have at least one stop"),
+            RasterError::InvalidGradientStops(positions) => write!(
+                f,
+                "Gradient stop positions must be finite and not decrease; got {:?}",
+                positions
+            ),
+            RasterError::NonFiniteGradientDirection(direction) => {
+                write!(f, "Gradient direction must be finite; got {:?}", direction)
+            }
+            RasterError::MiterLimitBelowOne(limit) => {
+                write!(
+                    f,
+                    "Miter limit must be finite and at least 1; got {}",
+                    limit
+                )
+            }
+            RasterError::InvalidDashes(dashes) => write!(
+                f,
+                "Dash lengths must be finite, not negative, and add up to more than zero; got {:?}",
+                dashes
+            ),
+            RasterError::Tessellation(e) => write!(f, "Tessellation failed: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for RasterError {}
+
+/// Rasters a vector path into triangles with the given method and default options.
+///
+/// Returns the vertices and the indices of the triangles, three per triangle.
 pub fn raster_path(
     builder: Builder,
     method: Method,
     color: LinSrgba,
 ) -> Result<(Vec<GpuVertex>, Vec<u32>)> {
+    raster_path_with_options(builder, method, color, RasterOptions::default())
+}
+
+/// Rasters a vector path into triangles with the given method and options.
+///
+/// Returns the vertices and the indices of the triangles, three per triangle.
+pub fn raster_path_with_options(
+    builder: Builder,
+    method: Method,
+    color: LinSrgba,
+    options: RasterOptions,
+) -> Result<(Vec<GpuVertex>, Vec<u32>)> {
+    if !(options.tolerance > 0. && options.tolerance.is_finite()) {
+        return Err(RasterError::NonPositiveTolerance(options.tolerance).into());
+    }
+    let path = builder.build();
     match method {
-        Method::Fill => {
-            let ctor = |v: P2, _: FillAttributes| -> P2 { v };
-            let mut buffers: VertexBuffers<P2, u32> = VertexBuffers::new();
-            let mut buffers_builder = BuffersBuilder::new(&mut buffers, ctor);
-
-            let mut tessellator = FillTessellator::new();
-            let result = tessellator.tessellate_path(
-                &builder.build(),
-                &FillOptions::default().with_tolerance(0.05),
-                &mut buffers_builder,
+        Method::Fill => fill(&path, color, options),
+        Method::GradientFill { stops, direction } => {
+            if stops.is_empty() {
+                return Err(RasterError::NoGradientStops.into());
+            }
+            if stops.iter().any(|(position, _)| !position.is_finite())
+                || stops.windows(2).any(|pair| pair[1].0 < pair[0].0)
+            {
+                let positions = stops.iter().map(|(position, _)| *position).collect();
+                return Err(RasterError::InvalidGradientStops(positions).into());
+            }
+            if !(direction.x.is_finite() && direction.y.is_finite()) {
+                return Err(RasterError::NonFiniteGradientDirection(direction).into());
+            }
+            let (mut vertices, indices) = fill(&path, color, options)?;
+            linear_gradient(&mut vertices, &stops, direction);
+            Ok((vertices, indices))
+        }
+        Method::Stroke(style) => stroke(&path, style, options, |_| color),
+        Method::GradientStroke { style, end } => {
+            stroke(&path, style, options, |t| color.mix(&end, t))
+        }
+        Method::FillAndStroke {
+            style,
+            stroke: stroke_color,
+        } => {
+            let (mut vertices, mut indices) = fill(&path, color, options)?;
+            let (stroke_vertices, stroke_indices) =
+                stroke(&path, style, options, |_| stroke_color)?;
+            let offset = vertices.len() as u32;
+            vertices.extend(stroke_vertices);
+            indices.extend(stroke_indices.into_iter().map(|i| i + offset));
+            Ok((vertices, indices))
+        }
+        Method::Hachure { angle, gap, width } => {
+            if !(gap > 0. && gap.is_finite()) {
+                return Err(RasterError::NonPositiveHachureGap(gap).into());
+            }
+            stroke(
+                &hachure_lines(&path, angle, gap, options.tolerance)?,
+                StrokeStyle::new(width),
+                options,
+                |_| color,
+            )
+        }
+    }
+}
+
+fn fill(
+    path: &Path,
+    color: LinSrgba,
+    options: RasterOptions,
+) -> Result<(Vec<GpuVertex>, Vec<u32>)> {
+    let ctor = |v: P2, _: FillAttributes| -> GpuVertex { colored(v, color) };
+    let mut buffers: VertexBuffers<GpuVertex, u32> = VertexBuffers::new();
+    let mut buffers_builder = BuffersBuilder::new(&mut buffers, ctor);
+
+    let mut tessellator = FillTessellator::new();
+    tessellator
+        .tessellate_path(
+            path,
+            &FillOptions::default()
+                .with_tolerance(options.tolerance)
+                .with_fill_rule(options.fill_rule.into()),
+            &mut buffers_builder,
+        )
+        .map_err(RasterError::Tessellation)?;
+
+    Ok((buffers.vertices, buffers.indices))
+}
+
+/// Strokes the path, coloring each vertex by its distance along the path as a fraction in [0, 1]
+/// of the path's length.
+///
+/// Distance runs on from one subpath into the next, as the tessellator measures advancement, so a
+/// path of several subpaths (or dashes) fades once across all of them rather than once per subpath.
+fn stroke(
+    path: &Path,
+    style: StrokeStyle,
+    options: RasterOptions,
+    color_at: impl Fn(f32) -> LinSrgba,
+) -> Result<(Vec<GpuVertex>, Vec<u32>)> {
+    if !style.width.is_finite() || style.width < 0. {
+        return Err(RasterError::InvalidStrokeWidth(style.width).into());
+    }
+    if !style.miter_limit.is_finite() || style.miter_limit < StrokeOptions::MINIMUM_MITER_LIMIT {
+        return Err(RasterError::MiterLimitBelowOne(style.miter_limit).into());
+    }
+    let dashed;
+    let path = if style.dashes.is_empty() {
+        path
+    } else {
+        if style.dashes.iter().any(|d| !d.is_finite() || *d < 0.)
+            || style.dashes.iter().sum::<f32>() <= 0.
+        {
+            return Err(RasterError::InvalidDashes(style.dashes).into());
+        }
+        dashed = dashes(path, &style.dashes, style.dash_offset, options.tolerance);
+        &dashed
+    };
+    let width = if style.width == 0. {
+        HAIRLINE_WIDTH
+    } else {
+        style.width
+    };
+
+    let length = length(path, options.tolerance);
+    let ctor = |v: P2, attributes: StrokeAttributes| -> GpuVertex {
+        let t = if length > 0. {
+            (attributes.advancement() / length).clamp(0., 1.)
+        } else {
+            0.
+        };
+        colored(v, color_at(t))
+    };
+    let mut buffers: VertexBuffers<GpuVertex, u32> = VertexBuffers::new();
+    let mut buffers_builder = BuffersBuilder::new(&mut buffers, ctor);
+
+    let mut tessellator = StrokeTessellator::new();
+    tessellator
+        .tessellate_path(
+            path,
+            &StrokeOptions::default()
+                .with_line_width(width)
+                .with_line_cap(style.cap.into())
+                .with_line_join(style.join.into())
+                .with_miter_limit(style.miter_limit)
+                .with_tolerance(options.tolerance),
+            &mut buffers_builder,
+        )
+        .map_err(RasterError::Tessellation)?;
+
+    Ok((buffers.vertices, buffers.indices))
+}
+
+/// Colors the vertices by a linear gradient along `direction` in their bounds normalized to the
+/// unit square. A zero direction colors every vertex with the first stop.
+fn linear_gradient(vertices: &mut [GpuVertex], stops: &[(f32, LinSrgba)], direction: V2) {
+    let (min, max) = vertices.iter().fold(
+        (
+            V2::new(f32::INFINITY, f32::INFINITY),
+            V2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+        ),
+        |(min, max), v| {
+            let p = V2::new(v.vpos[0], v.vpos[1]);
+            (min.min(p), max.max(p))
+        },
+    );
+    let extent = max - min;
+    let normalized = |p: f32, min: f32, extent: f32| {
+        if extent > 0. {
+            (p - min) / extent
+        } else {
+            0.
+        }
+    };
+    let axis = direction.square_length();
+
+    for v in vertices {
+        let t = if axis > 0. {
+            let uv = V2::new(
+                normalized(v.vpos[0], min.x, extent.x),
+                normalized(v.vpos[1], min.y, extent.y),
             );
-            match result {
-                Ok(_) => {}
-                Err(e) => panic!("Tessellation failed: {:?}", e),
-            }
-
-            Ok((
-                buffers
-                    .vertices
-                    .into_iter()
-                    .map(|v| GpuVertex {
-                        vpos: [v.x, v.y],
-                        vcol: [
-                            color.color.red,
-                            color.color.green,
-                            color.color.blue,
-                            color.alpha,
-                        ],
-                    })
-                    .collect(),
-                buffers.indices,
-            ))
-        }
-        Method::Stroke(width) => {
-            let ctor = |v: P2, _: StrokeAttributes| -> P2 { v };
-            let mut buffers: VertexBuffers<P2, u32> = VertexBuffers::new();
-            let mut buffers_builder = BuffersBuilder::new(&mut buffers, ctor);
-
-            let mut tessellator = StrokeTessellator::new();
-            tessellator
-                .tessellate_path(
-                    &builder.build(),
-                    &StrokeOptions::default()
-                        .with_line_width(width)
-                        .with_tolerance(0.05),
-                    &mut buffers_builder,
-                )
-                .expect("TODO: wrap error");
-
-            Ok((
-                buffers
-                    .vertices
-                    .into_iter()
-                    .map(|v| GpuVertex {
-                        vpos: [v.x, v.y],
-                        vcol: [
-                            color.color.red,
-                            color.color.green,
-                            color.color.blue,
-                            color.alpha,
-                        ],
-                    })
-                    .collect(),
-                buffers.indices,
-            ))
+            uv.dot(direction) / axis
+        } else {
+            stops[0].0
+        };
+        v.vcol = components(gradient_at(stops, t));
+    }
+}
+
+/// Returns the color of the gradient at `t`, between the stops on either side of it.
+fn gradient_at(stops: &[(f32, LinSrgba)], t: f32) -> LinSrgba {
+    let after = stops.iter().position(|(position, _)| *position > t);
+    match after {
+        Some(0) => stops[0].1,
+        Some(i) => {
+            let ((start, from), (end, to)) = (stops[i - 1], stops[i]);
+            from.mix(&to, (t - start) / (end - start))
+        }
+        None => stops[stops.len() - 1].1,
+    }
+}
+
+fn colored(v: P2, color: LinSrgba) -> GpuVertex {
+    GpuVertex {
+        vpos: [v.x, v.y],
+        vcol: components(color),
+        vdata: [0.; 4],
+    }
+}
+
+fn components(color: LinSrgba) -> [f32; 4] {
+    [
+        color.color.red,
+        color.color.green,
+        color.color.blue,
+        color.alpha,
+    ]
+}
+
+/// Returns the length of the path as the stroke tessellator measures it, over the flattened
+/// path including the closing edges of closed subpaths.
+fn length(path: &Path, tolerance: f32) -> f32 {
+    path.iter()
+        .flattened(tolerance)
+        .map(|event| match event {
+            PathEvent::Line { from, to } => (to - from).length(),
+            PathEvent::End {
+                last,
+                first,
+                close: true,
+            } => (first - last).length(),
+            _ => 0.,
+        })
+        .sum()
+}
+
+/// Returns the closed rings of a path, flattened into line segments.
+fn rings(path: &Path, tolerance: f32) -> Vec<Vec<P2>> {
+    let mut rings = vec![];
+    let mut ring = vec![];
+    for event in path.iter().flattened(tolerance) {
+        match event {
+            PathEvent::Begin { at } => ring = vec![at],
+            PathEvent::Line { to, .. } => ring.push(to),
+            PathEvent::End { .. } => rings.push(std::mem::take(&mut ring)),
+            _ => unreachable!("Flattened paths contain only lines"),
+        }
+    }
+    rings
+}
+
+/// Returns the dashes of a path as open subpaths, cut from the flattened path by the pattern of
+/// alternating dash and gap lengths. The pattern restarts `offset` into itself at the beginning
+/// of each subpath, and runs on through the closing edges of closed subpaths.
+fn dashes(path: &Path, pattern: &[f32], offset: f32, tolerance: f32) -> Path {
+    let pattern = if pattern.len() % 2 == 1 {
+        pattern.repeat(2)
+    } else {
+        pattern.to_vec()
+    };
+    let mut dasher = Dasher {
+        period: pattern.iter().sum(),
+        pattern,
+        index: 0,
+        remaining: 0.,
+        drawing: false,
+        dashes: Builder::new(),
+    };
+    for event in path.iter().flattened(tolerance) {
+        match event {
+            PathEvent::Begin { .. } => dasher.restart(offset),
+            PathEvent::Line { from, to } => dasher.segment(from, to),
+            PathEvent::End {
+                last,
+                first,
+                close: true,
+            } => dasher.segment(last, first),
+            _ => {}
+        }
+    }
+
+    dasher.dashes.build()
+}
+
+/// The state of cutting dashes along a path.
+struct Dasher {
+    pattern: Vec<f32>,
+    period: f32,
+    /// The index of the current length in the pattern; even indices are dashes.
+    index: usize,
+    /// How much of the current length is left.
+    remaining: f32,
+    /// Whether a dash subpath is open in the builder.
+    drawing: bool,
+    dashes: Builder,
+}
+
+impl Dasher {
+    fn restart(&mut self, offset: f32) {
+        let mut into = offset.rem_euclid(self.period);
+        self.index = 0;
+        while into >= self.pattern[self.index] {
+            into -= self.pattern[self.index];
+            self.index = (self.index + 1) % self.pattern.len();
+        }
+        self.remaining = self.pattern[self.index] - into;
+        self.drawing = false;
+    }
+
+    fn segment(&mut self, from: P2, to: P2) {
+        let length = (to - from).length();
+        let mut travelled = 0.;
+        while travelled < length {
+            let step = self.remaining.min(length - travelled);
+            if self.index & 1 == 0 {
+                if !self.drawing {
+                    self.dashes.move_to(from.lerp(to, travelled / length));
+                    self.drawing = true;
+                }
+                self.dashes
+                    .line_to(from.lerp(to, (travelled + step) / length));
+            }
+            travelled += step;
+            self.remaining -= step;
+            if self.remaining <= 0. {
+                self.drawing = false;
+                self.index = (self.index + 1) % self.pattern.len();
+                self.remaining = self.pattern[self.index];
+            }
+        }
+    }
+}
+
+/// Returns a path of parallel lines at the given angle, `gap` apart, covering the area inside
+/// the path under the even-odd rule.
+fn hachure_lines(path: &Path, angle: Angle, gap: f32, tolerance: f32) -> Result<Path> {
+    // Work in a space rotated so the lines are horizontal.
+    let origin = P2::new(0., 0.);
+    let rings = rings(path, tolerance)
+        .into_iter()
+        .map(|ring| {
+            ring.into_iter()
+                .map(|p| p.rotate(origin, -angle))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut lines = Builder::new();
+    let (min_y, max_y) = rings
+        .iter()
+        .flatten()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+            (min.min(p.y), max.max(p.y))
+        });
+    // Lines are placed by index, since adding the gap may not move y once it is small next to y.
+    let count = ((max_y - min_y) / gap - 0.5).ceil().max(0.);
+    if count > MAX_HACHURE_LINES as f32 {
+        return Err(RasterError::TooManyHachureLines(gap).into());
+    }
+    for i in 0..count as usize {
+        let y = min_y + (i as f32 + 0.5) * gap;
+        for span in crossings(&rings, y).chunks_exact(2) {
+            lines.move_to(P2::new(span[0], y).rotate(origin, angle));
+            lines.line_to(P2::new(span[1], y).rotate(origin, angle));
+        }
+    }
+
+    Ok(lines.build())
+}
+
+/// Returns the sorted x coordinates where a horizontal line at `y` crosses the edges of the rings.
+///
+/// Under the even-odd rule, consecutive pairs of crossings bound the spans inside the rings.
+fn crossings(rings: &[Vec<P2>], y: f32) -> Vec<f32> {
+    let mut crossings = edges(rings)
+        .filter(|(a, b)| (a.y <= y) != (b.y <= y))
+        .map(|(a, b)| a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x))
+        .filter(|x| x.is_finite())
+        .collect::<Vec<_>>();
+    crossings.sort_by(f32::total_cmp);
+    crossings
+}
+
+/// Returns the edges of the rings, including the edge closing each.
+fn edges<'a>(rings: &'a [Vec<P2>]) -> impl Iterator<Item = (P2, P2)> + 'a {
+    rings.iter().flat_map(|ring| {
+        ring.iter()
+            .copied()
+            .zip(ring.iter().copied().cycle().skip(1))
+    })
+}
+
+/// Edges bucketed by the rows of pixels their y range overlaps, so each scanline only tests the
+/// edges which can cross it.
+struct RowIndex {
+    rows: Vec<Vec<(P2, P2)>>,
+}
+
+impl RowIndex {
+    fn new(edges: impl Iterator<Item = (P2, P2)>, min_y: f32, height: u32) -> Self {
+        let mut rows = vec![vec![]; height as usize];
+        if height == 0 {
+            return Self { rows };
+        }
+        for (a, b) in edges {
+            let (low, high) = (a.y.min(b.y) - min_y, a.y.max(b.y) - min_y);
+            if high < 0. || low >= height as f32 {
+                continue;
+            }
+            let first = low.max(0.) as usize;
+            let last = (high as usize).min(height as usize - 1);
+            for row in &mut rows[first..=last] {
+                row.push((a, b));
+            }
+        }
+        Self { rows }
+    }
+
+    /// Returns the edges which may cross scanlines at any y in the row.
+    fn row(&self, row: usize) -> impl Iterator<Item = (P2, P2)> + '_ {
+        self.rows[row].iter().copied()
+    }
+}
+
+/// Returns the spans of a horizontal line at `y` which are inside the edges under the fill rule.
+fn spans(edges: impl Iterator<Item = (P2, P2)>, y: f32, fill_rule: FillRule) -> Vec<(f32, f32)> {
+    let mut crossings = edges
+        .filter(|(a, b)| (a.y <= y) != (b.y <= y))
+        .map(|(a, b)| {
+            let x = a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x);
+            (x, if b.y > a.y { 1 } else { -1 })
+        })
+        .collect::<Vec<_>>();
+    crossings.retain(|(x, _)| x.is_finite());
+    crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let inside = |winding: i32| match fill_rule {
+        FillRule::EvenOdd => winding & 1 == 1,
+        FillRule::NonZero => winding != 0,
+    };
+    let mut spans = vec![];
+    let mut winding = 0;
+    let mut start = 0.;
+    for (x, direction) in crossings {
+        let was_inside = inside(winding);
+        winding += direction;
+        match (was_inside, inside(winding)) {
+            (false, true) => start = x,
+            (true, false) => spans.push((start, x)),
+            _ => {}
+        }
+    }
+    spans
+}
+
+/// Returns the fraction of each pixel of the bounds which the path covers under the fill rule,
+/// in rows from the top of the bounds, computed on the CPU.
+///
+/// There is a pixel for each unit of the bounds. Each samples coverage on a `samples` by
+/// `samples` grid, so pixels on the edges of the path get fractional coverage.
+pub fn coverage(path: &Path, bounds: Bounds, samples: u32, fill_rule: FillRule) -> Vec<f32> {
+    let (width, height) = (
+        bounds.size.width.ceil() as u32,
+        bounds.size.height.ceil() as u32,
+    );
+    let samples = samples.max(1);
+    let rings = rings(path, TOLERANCE);
+    let offset = |i: u32, k: u32| i as f32 + (k as f32 + 0.5) / samples as f32;
+
+    let mut coverage = vec![0.; (width * height) as usize];
+    if width == 0 || height == 0 {
+        return coverage;
+    }
+    let index = RowIndex::new(edges(&rings), bounds.min_y(), height);
+    // Rows are independent, so they are covered in parallel.
+    coverage
+        .par_chunks_mut(width as usize)
+        .enumerate()
+        .for_each(|(row, pixels)| {
+            let mut covered = vec![0u32; width as usize];
+            for k in 0..samples {
+                let y = bounds.min_y() + offset(row as u32, k);
+                for (start, end) in spans(index.row(row), y, fill_rule) {
+                    let (start, end) = (start - bounds.min_x(), end - bounds.min_x());
+                    let first = ((start * samples as f32 - 0.5).ceil().max(0.)) as u32;
+                    let last =
+                        ((end * samples as f32 - 0.5).ceil().max(0.) as u32).min(width * samples);
+                    for sample in first..last {
+                        covered[(sample / samples) as usize] += 1;
+                    }
+                }
+            }
+            for (pixel, covered) in pixels.iter_mut().zip(covered) {
+                *pixel = covered as f32 / (samples * samples) as f32;
+            }
+        });
+    coverage
+}
+
+/// Fills a path into an image on the CPU, without a GPU, so output is the same on every machine.
+///
+/// The image has a pixel for each unit of the bounds, with its first row at the top of the
+/// bounds. Each pixel takes the color with its alpha scaled by its `coverage` under the even-odd
+/// rule. Colors are encoded as sRGB, as in saved frames.
+pub fn rasterize_aa(
+    path: &Path,
+    color: LinSrgba,
+    bounds: Bounds,
+    samples: u32,
+) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let width = bounds.size.width.ceil() as u32;
+    let height = bounds.size.height.ceil() as u32;
+    let coverage = coverage(path, bounds, samples, FillRule::EvenOdd);
+
+    let encode = |c: f32| -> u8 { <Srgb as TransferFn>::from_linear(c).convert::<u8>() };
+    let (red, green, blue) = (
+        encode(color.color.red),
+        encode(color.color.green),
+        encode(color.color.blue),
+    );
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let covered = coverage[(y * width + x) as usize];
+        Rgba([red, green, blue, (color.alpha * covered).convert::<u8>()])
+    })
+}
+
+/// Returns an iterator over the triangles of geometry rastered by `raster_path`.
+pub fn triangles<'a>(
+    vertices: &'a [GpuVertex],
+    indices: &'a [u32],
+) -> impl Iterator<Item = [P2; 3]> + 'a {
+    let position = move |i: u32| {
+        let [x, y] = vertices[i as usize].vpos;
+        P2::new(x, y)
+    };
+    indices
+        .chunks_exact(3)
+        .map(move |t| [position(t[0]), position(t[1]), position(t[2])])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PI;
+    use palette::{Alpha, LinSrgb};
+
+    fn white() -> LinSrgba {
+        Alpha::<LinSrgb, _>::new(1., 1., 1., 1.)
+    }
+
+    fn signed_area([a, b, c]: [P2; 3]) -> f32 {
+        (b - a).cross(c - a) / 2.
+    }
+
+    #[test]
+    fn quad_triangles() {
+        let mut builder = Builder::new();
+        builder.move_to(P2::new(0., 0.));
+        builder.line_to(P2::new(2., 0.));
+        builder.line_to(P2::new(2., 2.));
+        builder.line_to(P2::new(0., 2.));
+        builder.close();
+
+        let (vertices, indices) = raster_path(builder, Method::Fill, white()).unwrap();
+        let areas = triangles(&vertices, &indices)
+            .map(signed_area)
+            .collect::<Vec<_>>();
+
+        assert_eq!(areas.len(), 2);
+        assert!(areas.iter().all(|a| a.signum() == areas[0].signum()));
+        assert!((areas.iter().sum::<f32>().abs() - 4.).abs() < 0.001);
+    }
+
+    fn line() -> Builder {
+        let mut builder = Builder::new();
+        builder.move_to(P2::new(0., 0.));
+        builder.line_to(P2::new(10., 0.));
+        builder
+    }
+
+    fn square() -> Builder {
+        let mut builder = line();
+        builder.line_to(P2::new(10., 10.));
+        builder.line_to(P2::new(0., 10.));
+        builder.close();
+        builder
+    }
+
+    #[test]
+    fn zero_width_stroke_is_hairline() {
+        let (vertices, indices) =
+            raster_path(line(), Method::Stroke(StrokeStyle::new(0.)), white()).unwrap();
+        let (hairline_vertices, _) = raster_path(
+            line(),
+            Method::Stroke(StrokeStyle::new(HAIRLINE_WIDTH)),
+            white(),
+        )
+        .unwrap();
+
+        assert!(!indices.is_empty());
+        assert_eq!(
+            vertices.iter().map(|v| v.vpos).collect::<Vec<_>>(),
+            hairline_vertices.iter().map(|v| v.vpos).collect::<Vec<_>>()
+        );
+    }
+
+    fn lines(path: &Path) -> Vec<(P2, P2)> {
+        path.iter()
+            .filter_map(|event| match event {
+                PathEvent::Line { from, to } => Some((from, to)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stroke_caps() {
+        let vertices = |cap| {
+            let style = StrokeStyle::new(2.).with_cap(cap);
+            let (vertices, _) = raster_path(line(), Method::Stroke(style), white()).unwrap();
+            vertices
+        };
+        let max_x = |vertices: &[GpuVertex]| {
+            vertices
+                .iter()
+                .map(|v| v.vpos[0])
+                .fold(f32::NEG_INFINITY, f32::max)
+        };
+
+        let (butt, round, square) = (
+            vertices(Cap::Butt),
+            vertices(Cap::Round),
+            vertices(Cap::Square),
+        );
+        assert!(round.len() > butt.len());
+        assert!((max_x(&butt) - 10.).abs() < 0.001);
+        assert!((max_x(&round) - 11.).abs() < 0.001);
+        assert!((max_x(&square) - 11.).abs() < 0.001);
+    }
+
+    #[test]
+    fn stroke_joins() {
+        let corner = || {
+            let mut builder = Builder::new();
+            builder.move_to(P2::new(0., 0.));
+            builder.line_to(P2::new(10., 0.));
+            builder.line_to(P2::new(0., 2.));
+            builder
+        };
+        let max_x = |style: StrokeStyle| {
+            let (vertices, _) = raster_path(corner(), Method::Stroke(style), white()).unwrap();
+            vertices
+                .iter()
+                .map(|v| v.vpos[0])
+                .fold(f32::NEG_INFINITY, f32::max)
+        };
+
+        // The corner is sharp enough that its miter reaches about 10 widths past the vertex.
+        let miter = StrokeStyle::new(1.).with_miter_limit(20.);
+        assert!(max_x(miter.clone()) > 14.);
+        assert!(max_x(miter.clone().with_join(Join::Bevel)) < 10.6);
+        assert!(max_x(miter.clone().with_miter_limit(2.)) < 10.6);
+        assert!(max_x(miter.with_join(Join::Round)) < 10.6);
+    }
+
+    #[test]
+    fn stroke_dashes() {
+        let spans = |pattern: &[f32], offset: f32| {
+            dashes(&line().build(), pattern, offset, TOLERANCE)
+                .iter()
+                .filter_map(|event| match event {
+                    PathEvent::End { last, first, .. } => Some((first.x, last.x)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            spans(&[2., 1.], 0.),
+            vec![(0., 2.), (3., 5.), (6., 8.), (9., 10.)]
+        );
+        assert_eq!(
+            spans(&[2., 1.], 1.),
+            vec![(0., 1.), (2., 4.), (5., 7.), (8., 10.)]
+        );
+        assert_eq!(spans(&[2.], 0.), vec![(0., 2.), (4., 6.), (8., 10.)]);
+
+        let style = StrokeStyle::new(1.).with_dashes(vec![2., 1.], 0.);
+        let (solid, _) =
+            raster_path(line(), Method::Stroke(StrokeStyle::new(1.)), white()).unwrap();
+        let (dashed, _) = raster_path(line(), Method::Stroke(style), white()).unwrap();
+        assert!(dashed.len() > solid.len());
+        let in_gap = |x: f32| {
+            [2., 5., 8.]
+                .iter()
+                .any(|gap| x > gap + 0.001 && x < gap + 0.999)
+        };
+        assert!(dashed.iter().all(|v| !in_gap(v.vpos[0])));
+
+        let invalid = StrokeStyle::new(1.).with_dashes(vec![1., -1.], 0.);
+        assert!(raster_path(line(), Method::Stroke(invalid), white()).is_err());
+    }
+
+    #[test]
+    fn hachure() {
+        let mut builder = Builder::new();
+        builder.move_to(P2::new(0., 0.));
+        builder.line_to(P2::new(10., 0.));
+        builder.line_to(P2::new(10., 10.));
+        builder.line_to(P2::new(0., 10.));
+        builder.close();
+        let square = builder.build();
+
+        let horizontal = lines(&hachure_lines(&square, Angle::radians(0.), 2., TOLERANCE).unwrap());
+        assert_eq!(horizontal.len(), 5);
+        for (i, (from, to)) in horizontal.into_iter().enumerate() {
+            let y = 1. + i as f32 * 2.;
+            assert!((from - P2::new(0., y)).length() < 0.001);
+            assert!((to - P2::new(10., y)).length() < 0.001);
+        }
+
+        let diagonal =
+            lines(&hachure_lines(&square, Angle::radians(PI / 4.), 1., TOLERANCE).unwrap());
+        assert!(!diagonal.is_empty());
+        for (from, to) in diagonal {
+            let direction = (to - from).normalize();
+            assert!((direction.x - direction.y).abs() < 0.001);
+            for p in &[from, to] {
+                assert!(p.x > -0.001 && p.x < 10.001 && p.y > -0.001 && p.y < 10.001);
+            }
+        }
+    }
+
+    #[test]
+    fn hachure_line_counts_are_bounded() {
+        let rectangle = |min_y: f32, height: f32| {
+            let mut builder = Builder::new();
+            builder.move_to(P2::new(0., min_y));
+            builder.line_to(P2::new(10., min_y));
+            builder.line_to(P2::new(10., min_y + height));
+            builder.line_to(P2::new(0., min_y + height));
+            builder.close();
+            builder.build()
+        };
+        let horizontal = |path: &Path, gap: f32| {
+            hachure_lines(path, Angle::radians(0.), gap, TOLERANCE).map(|path| lines(&path))
+        };
+
+        // Far from the origin the gap is smaller than the spacing of f32 values, so stepping y by
+        // it would never finish. Lines rounded onto the rectangle's edges may be dropped.
+        let far = horizontal(&rectangle(1e8, 64.), 1.).unwrap();
+        assert!(!far.is_empty() && far.len() <= 64, "{}", far.len());
+
+        let error = horizontal(&rectangle(0., 1000.), 1e-4).unwrap_err();
+        assert!(matches!(
+            error.downcast::<RasterError>().unwrap(),
+            RasterError::TooManyHachureLines(_)
+        ));
+
+        // Non-finite crossings, as from a ring whose rotation overflowed, are left out rather
+        // than failing to sort.
+        let ring = vec![
+            P2::new(0., 0.),
+            P2::new(f32::NAN, 5.),
+            P2::new(10., 10.),
+            P2::new(10., 0.),
+        ];
+        assert_eq!(crossings(std::slice::from_ref(&ring), 2.), vec![10.]);
+        assert_eq!(spans(edges(&[ring]), 2., FillRule::EvenOdd), vec![]);
+    }
+
+    #[test]
+    fn fill_and_stroke() {
+        let fill_color = white();
+        let stroke_color = Alpha::<LinSrgb, _>::new(1., 0., 0., 1.);
+        let style = StrokeStyle::new(2.);
+        let (filled, filled_indices) = raster_path(square(), Method::Fill, fill_color).unwrap();
+        let (stroked, stroked_indices) =
+            raster_path(square(), Method::Stroke(style.clone()), stroke_color).unwrap();
+        let (vertices, indices) = raster_path(
+            square(),
+            Method::FillAndStroke {
+                style,
+                stroke: stroke_color,
+            },
+            fill_color,
+        )
+        .unwrap();
+
+        assert_eq!(vertices.len(), filled.len() + stroked.len());
+        assert_eq!(indices.len(), filled_indices.len() + stroked_indices.len());
+        let (fill_part, stroke_part) = vertices.split_at(filled.len());
+        assert!(fill_part.iter().all(|v| v.vcol == [1., 1., 1., 1.]));
+        assert!(stroke_part.iter().all(|v| v.vcol == [1., 0., 0., 1.]));
+
+        // Stroke triangles come after the fill's and index only stroke vertices.
+        let (fill_indices, stroke_indices) = indices.split_at(filled_indices.len());
+        assert!(fill_indices.iter().all(|&i| (i as usize) < filled.len()));
+        assert!(stroke_indices
+            .iter()
+            .all(|&i| (i as usize) >= filled.len() && (i as usize) < vertices.len()));
+    }
+
+    #[test]
+    fn gradient_stroke_follows_length() {
+        let start = Alpha::<LinSrgb, _>::new(1., 0., 0., 1.);
+        let end = Alpha::<LinSrgb, _>::new(0., 0., 1., 0.5);
+        let (vertices, _) = raster_path(
+            line(),
+            Method::GradientStroke {
+                style: StrokeStyle::new(2.),
+                end,
+            },
+            start,
+        )
+        .unwrap();
+
+        let color = |c: LinSrgba| [c.color.red, c.color.green, c.color.blue, c.alpha];
+        assert!(!vertices.is_empty());
+        for v in vertices {
+            let expected = match v.vpos[0] {
+                x if x < 0.001 => color(start),
+                x if x > 9.999 => color(end),
+                x => color(start.mix(&end, x / 10.)),
+            };
+            for (actual, expected) in v.vcol.iter().zip(&expected) {
+                assert!((actual - expected).abs() < 0.001);
+            }
+        }
+
+        // A second subpath continues the ramp where the first left off, so the two lines
+        // together fade once from start to end.
+        let mut builder = line();
+        builder.move_to(P2::new(0., 5.));
+        builder.line_to(P2::new(10., 5.));
+        let (vertices, _) = raster_path(
+            builder,
+            Method::GradientStroke {
+                style: StrokeStyle::new(2.),
+                end,
+            },
+            start,
+        )
+        .unwrap();
+        assert!(vertices.iter().any(|v| v.vpos[1] > 2.5));
+        for v in vertices {
+            let x = v.vpos[0].clamp(0., 10.);
+            let t = (if v.vpos[1] > 2.5 { 10. + x } else { x }) / 20.;
+            for (actual, expected) in v.vcol.iter().zip(&color(start.mix(&end, t))) {
+                assert!((actual - expected).abs() < 0.001, "{:?}", v);
+            }
+        }
+    }
+
+    #[test]
+    fn quadratic_curves() {
+        let arch = || {
+            let mut builder = Builder::new();
+            builder.move_to(P2::new(0., 0.));
+            builder.quadratic_bezier_to(P2::new(5., 10.), P2::new(10., 0.));
+            builder
+        };
+
+        let mut closed = arch();
+        closed.close();
+        let (vertices, indices) = raster_path(closed, Method::Fill, white()).unwrap();
+        assert!((8..200).contains(&vertices.len()));
+        // A parabolic segment covers two thirds of its bounding box, less what flattening cuts
+        // off within the tolerance along its length.
+        let area = triangles(&vertices, &indices)
+            .map(signed_area)
+            .sum::<f32>()
+            .abs();
+        assert!((area - 2. / 3. * 50.).abs() < TOLERANCE * 15.);
+
+        let (vertices, _) =
+            raster_path(arch(), Method::Stroke(StrokeStyle::new(1.)), white()).unwrap();
+        assert!((8..400).contains(&vertices.len()));
+        assert!(vertices.iter().all(|v| v.vpos[1] <= 5.5));
+    }
+
+    #[test]
+    fn cubic_curve_crossings() {
+        let hump = || {
+            let mut builder = Builder::new();
+            builder.move_to(P2::new(0., 0.));
+            builder.cubic_bezier_to(P2::new(0., 20.), P2::new(20., 20.), P2::new(20., 0.));
+            builder.close();
+            builder
+        };
+        let rings = rings(&hump().build(), TOLERANCE);
+
+        // The curve is at height 7.5 where t(1 - t) = 1/8, which is at x = 20 (3t^2 - 2t^3).
+        let t = (1. - 0.5f32.sqrt()) / 2.;
+        let x = 20. * (3. * t * t - 2. * t * t * t);
+        let at_half_height = crossings(&rings, 7.5);
+        assert_eq!(at_half_height.len(), 2);
+        assert!((at_half_height[0] - x).abs() < 0.1);
+        assert!((at_half_height[1] - (20. - x)).abs() < 0.1);
+        assert!(crossings(&rings, 15.5).is_empty());
+
+        let (vertices, _) = raster_path(hump(), Method::Fill, white()).unwrap();
+        assert!(vertices.iter().all(|v| v.vpos[1] <= 15.));
+    }
+
+    #[test]
+    fn tolerance_sets_detail() {
+        let circle = || {
+            let mut builder = Builder::new();
+            builder.move_to(P2::new(50., 0.));
+            builder.arc(
+                P2::new(0., 0.),
+                crate::V2::new(50., 50.),
+                Angle::radians(2. * PI),
+                Angle::radians(0.),
+            );
+            builder.close();
+            builder
+        };
+        let vertices = |method: Method, tolerance| {
+            let options = RasterOptions {
+                tolerance,
+                ..RasterOptions::default()
+            };
+            raster_path_with_options(circle(), method, white(), options)
+                .unwrap()
+                .0
+                .len()
+        };
+
+        for method in &[Method::Fill, Method::Stroke(StrokeStyle::new(1.))] {
+            assert!(vertices(method.clone(), 0.01) > vertices(method.clone(), 0.1));
+            assert_eq!(
+                vertices(method.clone(), TOLERANCE),
+                raster_path(circle(), method.clone(), white())
+                    .unwrap()
+                    .0
+                    .len()
+            );
+        }
+        assert!(raster_path_with_options(
+            circle(),
+            Method::Fill,
+            white(),
+            RasterOptions {
+                tolerance: 0.,
+                ..RasterOptions::default()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn fill_rules() {
+        let star = || {
+            let mut builder = Builder::new();
+            let point = |i: usize| {
+                let angle = Angle::radians(i as f32 * 4. * PI / 5.);
+                P2::new(10. * angle.get().cos(), 10. * angle.get().sin())
+            };
+            builder.move_to(point(0));
+            (1..5).for_each(|i| {
+                builder.line_to(point(i));
+            });
+            builder.close();
+            builder
+        };
+        let covers = |fill_rule, p: P2| {
+            let options = RasterOptions {
+                fill_rule,
+                ..RasterOptions::default()
+            };
+            let (vertices, indices) =
+                raster_path_with_options(star(), Method::Fill, white(), options).unwrap();
+            let covered = triangles(&vertices, &indices).any(|[a, b, c]| {
+                let sides = [
+                    signed_area([a, b, p]),
+                    signed_area([b, c, p]),
+                    signed_area([c, a, p]),
+                ];
+                sides.iter().all(|s| *s > 0.) || sides.iter().all(|s| *s < 0.)
+            });
+            covered
+        };
+
+        let (center, point) = (P2::new(0., 0.), P2::new(8., 0.));
+        assert!(!covers(FillRule::EvenOdd, center));
+        assert!(covers(FillRule::EvenOdd, point));
+        assert!(covers(FillRule::NonZero, center));
+        assert!(covers(FillRule::NonZero, point));
+    }
+
+    #[test]
+    fn gradient_fill() {
+        let square = || {
+            let mut builder = Builder::new();
+            builder.move_to(P2::new(10., 10.));
+            builder.line_to(P2::new(30., 10.));
+            builder.line_to(P2::new(30., 30.));
+            builder.line_to(P2::new(10., 30.));
+            builder.close();
+            builder
+        };
+        let red = Alpha::<LinSrgb, _>::new(1., 0., 0., 1.);
+        let blue = Alpha::<LinSrgb, _>::new(0., 0., 1., 1.);
+        let color = |c: LinSrgba| [c.color.red, c.color.green, c.color.blue, c.alpha];
+        let gradient = |stops: Vec<(f32, LinSrgba)>, direction| {
+            let method = Method::GradientFill { stops, direction };
+            raster_path(square(), method, white()).unwrap().0
+        };
+
+        let vertices = gradient(vec![(0., red), (1., blue)], V2::new(1., 0.));
+        assert!(!vertices.is_empty());
+        for v in vertices {
+            let expected = if v.vpos[0] < 20. { red } else { blue };
+            assert_eq!(v.vcol, color(expected));
+        }
+
+        // Stops past the ends of the gradient clamp, and the middle follows the stops between.
+        let vertices = gradient(vec![(0.25, red), (0.75, blue)], V2::new(1., 1.));
+        for v in vertices {
+            let expected = match (v.vpos[0] < 20., v.vpos[1] < 20.) {
+                (true, true) => red,
+                (false, false) => blue,
+                _ => red.mix(&blue, 0.5),
+            };
+            for (actual, expected) in v.vcol.iter().zip(&color(expected)) {
+                assert!((actual - expected).abs() < 0.001);
+            }
+        }
+
+        let vertices = gradient(vec![(0., red), (1., blue)], V2::zero());
+        assert!(vertices.iter().all(|v| v.vcol == color(red)));
+        let empty = Method::GradientFill {
+            stops: vec![],
+            direction: V2::new(1., 0.),
+        };
+        assert!(raster_path(square(), empty, white()).is_err());
+
+        let error = |stops: Vec<(f32, LinSrgba)>, direction| {
+            let method = Method::GradientFill { stops, direction };
+            raster_path(square(), method, white())
+                .unwrap_err()
+                .downcast::<RasterError>()
+                .unwrap()
+        };
+        let right = V2::new(1., 0.);
+        assert_eq!(
+            error(vec![(0.5, red), (0.25, blue)], right),
+            RasterError::InvalidGradientStops(vec![0.5, 0.25])
+        );
+        assert!(matches!(
+            error(vec![(0., red), (f32::NAN, blue)], right),
+            RasterError::InvalidGradientStops(_)
+        ));
+        assert!(matches!(
+            error(vec![(0., red), (1., blue)], V2::new(f32::NAN, 0.)),
+            RasterError::NonFiniteGradientDirection(_)
+        ));
+        assert!(raster_path(
+            square(),
+            Method::GradientFill {
+                stops: vec![(0., red), (0.5, blue), (0.5, red)],
+                direction: right,
+            },
+            white()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn cpu_raster_coverage() {
+        let bounds = Bounds::new(P2::new(-10., -10.), crate::S2::new(20., 20.));
+        let mut builder = Builder::new();
+        builder.move_to(P2::new(-2.5, -10.));
+        builder.line_to(P2::new(10., -10.));
+        builder.line_to(P2::new(10., 10.));
+        builder.line_to(P2::new(-2.5, 10.));
+        builder.close();
+        let half_plane = rasterize_aa(&builder.build(), white(), bounds, 8);
+
+        for (x, _, pixel) in half_plane.enumerate_pixels() {
+            let expected = match x {
+                x if x < 7 => 0,
+                7 => 128,
+                _ => 255,
+            };
+            assert!((pixel[3] as i32 - expected).abs() <= 1);
+        }
+
+        let mut builder = Builder::new();
+        builder.move_to(P2::new(7., 0.));
+        builder.arc(
+            P2::new(0., 0.),
+            crate::V2::new(7., 7.),
+            Angle::radians(2. * PI),
+            Angle::radians(0.),
+        );
+        builder.close();
+        let circle = rasterize_aa(&builder.build(), white(), bounds, 16);
+
+        let area = circle
+            .pixels()
+            .map(|pixel| pixel[3] as f32 / 255.)
+            .sum::<f32>();
+        assert!((area - PI * 49.).abs() < PI * 49. * 0.01);
+        for (x, y, pixel) in circle.enumerate_pixels() {
+            let center = P2::new(x as f32 - 9.5, y as f32 - 9.5);
+            let distance = center.to_vector().length();
+            if distance < 6. {
+                assert_eq!(pixel[3], 255);
+            } else if distance > 8. {
+                assert_eq!(pixel[3], 0);
+            } else {
+                // Coverage of a pixel straddling the edge is about how far inside its center is.
+                let expected = (7. - distance + 0.5).clamp(0., 1.) * 255.;
+                assert!((pixel[3] as f32 - expected).abs() < 64.);
+            }
+        }
+    }
+
+    #[test]
+    fn cpu_coverage() {
+        let rectangle = |builder: &mut Builder, min: P2, max: P2| {
+            builder.move_to(min);
+            builder.line_to(P2::new(max.x, min.y));
+            builder.line_to(max);
+            builder.line_to(P2::new(min.x, max.y));
+            builder.close();
+        };
+        let bounds = Bounds::new(P2::new(0., 0.), crate::S2::new(8., 6.));
+        let mut builder = Builder::new();
+        rectangle(&mut builder, P2::new(1.5, 1.25), P2::new(6.5, 4.75));
+        let rectangle_coverage = coverage(&builder.build(), bounds, 4, FillRule::NonZero);
+
+        let expected = |x: usize, y: usize| {
+            let column = match x {
+                0 | 7 => 0.,
+                1 | 6 => 0.5,
+                _ => 1.,
+            };
+            let row = match y {
+                0 | 5 => 0.,
+                1 | 4 => 0.75,
+                _ => 1.,
+            };
+            column * row
+        };
+        for (i, covered) in rectangle_coverage.iter().enumerate() {
+            assert_eq!(*covered, expected(i % 8, i / 8), "pixel {}", i);
+        }
+
+        // Overlapping rectangles wound the same way leave a hole only under even-odd.
+        let mut builder = Builder::new();
+        rectangle(&mut builder, P2::new(0., 0.), P2::new(5., 6.));
+        rectangle(&mut builder, P2::new(3., 0.), P2::new(8., 6.));
+        let path = builder.build();
+        let overlap = 2 * 8 + 4;
+        assert_eq!(coverage(&path, bounds, 2, FillRule::NonZero)[overlap], 1.);
+        assert_eq!(coverage(&path, bounds, 2, FillRule::EvenOdd)[overlap], 0.);
+        assert_eq!(coverage(&path, bounds, 2, FillRule::EvenOdd)[8 + 1], 1.);
+
+        for empty in &[
+            Bounds::new(P2::new(0., 0.), crate::S2::new(10., 0.)),
+            Bounds::new(P2::new(0., 0.), crate::S2::new(0., 10.)),
+        ] {
+            assert!(coverage(&path, *empty, 2, FillRule::NonZero).is_empty());
+        }
+    }
+
+    #[test]
+    fn row_index_finds_crossing_edges() {
+        let rings = vec![(0..5000)
+            .map(|i| {
+                let angle = i as f32 * 1999. / 5000. * 2. * PI;
+                P2::new(50. + angle.cos() * 45., 40. + angle.sin() * 35.)
+            })
+            .collect::<Vec<_>>()];
+        let index = RowIndex::new(edges(&rings), 0.5, 80);
+
+        for row in 0..80 {
+            for &k in &[0., 0.25, 0.999] {
+                let y = 0.5 + row as f32 + k;
+                for &rule in &[FillRule::EvenOdd, FillRule::NonZero] {
+                    assert_eq!(
+                        spans(index.row(row), y, rule),
+                        spans(edges(&rings), y, rule)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cpu_coverage_rows_match() {
+        let mut builder = Builder::new();
+        for i in 0..=97 {
+            let angle = i as f32 * 37. / 97. * 2. * PI;
+            let point = P2::new(50. + angle.cos() * 45., 40. + angle.sin() * 35.);
+            if i == 0 {
+                builder.move_to(point);
+            } else {
+                builder.line_to(point);
+            }
+        }
+        builder.close();
+        let path = builder.build();
+        let bounds = Bounds::new(P2::new(0., 0.), crate::S2::new(100., 80.));
+
+        for &rule in &[FillRule::EvenOdd, FillRule::NonZero] {
+            let all_rows = coverage(&path, bounds, 4, rule);
+            let row_by_row = (0..80)
+                .flat_map(|row| {
+                    let row_bounds = Bounds::new(P2::new(0., row as f32), crate::S2::new(100., 1.));
+                    coverage(&path, row_bounds, 4, rule)
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(all_rows, row_by_row);
+        }
+    }
+
+    #[test]
+    fn negative_width_stroke_is_error() {
+        let error =
+            raster_path(line(), Method::Stroke(StrokeStyle::new(-1.)), white()).unwrap_err();
+        match error.downcast::<RasterError>() {
+            Ok(RasterError::InvalidStrokeWidth(width)) => assert_eq!(width, -1.),
+            e => panic!("Unexpected result: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn non_finite_stroke_parameters_are_errors() {
+        let error = |method: Method| {
+            raster_path(line(), method, white())
+                .unwrap_err()
+                .downcast::<RasterError>()
+                .expect("a raster error")
+        };
+        for bad in &[f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                error(Method::Stroke(StrokeStyle::new(*bad))),
+                RasterError::InvalidStrokeWidth(_)
+            ));
+            let style = StrokeStyle {
+                miter_limit: *bad,
+                ..StrokeStyle::new(1.)
+            };
+            assert!(matches!(
+                error(Method::Stroke(style)),
+                RasterError::MiterLimitBelowOne(_)
+            ));
+            let hachure = Method::Hachure {
+                angle: Angle::radians(0.),
+                gap: *bad,
+                width: 1.,
+            };
+            assert!(matches!(
+                error(hachure),
+                RasterError::NonPositiveHachureGap(_)
+            ));
         }
     }
 }
@@ -1,38 +1,139 @@
 //! Canvas rendering.
 
-use crate::{canvas::*, gpu::*, paint::*, uniforms::*, Options, Result, World};
+use crate::{
+    canvas::*, gpu::*, paint::*, uniforms::*, video::FrameEncoder, Options, Result, World,
+};
 use glium::{
     glutin::EventsLoop,
     texture::texture2d_multisample::Texture2dMultisample,
     texture::{Dimensions, MipmapsOption},
-    Frame, GlObject, Program,
+    Frame, GlObject, Program, Surface,
 };
-use image::{ImageBuffer, Rgba};
-use palette::{
-    encoding::{srgb::Srgb, TransferFn},
-    Component,
+use rand::{random, RngCore};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
 };
-use rand::{random, rngs::StdRng};
-use rayon::prelude::*;
-use std::{path::PathBuf, rc::Rc, time::Duration};
+
+/// The number of recent frames whose render times are kept for `Context::render_times`.
+const RENDER_TIME_HISTORY: usize = 120;
+
+/// How often input is checked while paused.
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(16);
 
 /// The context of the current render frame.
-#[derive(Debug)]
 pub struct Context<'a> {
     /// A random number generator. This is shared between frames.
     ///
-    /// To branch the rng, keep a clone.
-    pub rng: &'a mut StdRng,
+    /// Any generator can stand in for it, such as a fixed sequence in a test. To branch the rng,
+    /// seed a new generator from it, as `Layered` does.
+    pub rng: &'a mut dyn RngCore,
     /// The world in which painting takes place.
     pub world: World,
+    /// The options of the run.
+    pub options: &'a Options,
     /// The current frame in the composition.
     pub frame: usize,
     /// The elapsed time in the composition.
     pub time: Duration,
+    /// How long each of the most recent frames took to paint and render, oldest first.
+    ///
+    /// This can be used to adapt the level of detail to keep up with the frame rate.
+    pub render_times: &'a [Duration],
+}
+
+impl<'a> fmt::Debug for Context<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("world", &self.world)
+            .field("frame", &self.frame)
+            .field("time", &self.time)
+            .field("render_times", &self.render_times)
+            .finish()
+    }
 }
 
+impl<'a> Context<'a> {
+    /// Writes the options of the run, pinned to this frame's world and seed, to a file that
+    /// `Options::from_file` can load to reproduce the painting starting from this frame.
+    pub fn export_config(&self, path: impl AsRef<Path>) -> Result<()> {
+        Options {
+            world: self.world,
+            start_frame: self.frame,
+            ..self.options.for_seed(self.world.seed)
+        }
+        .export(path)
+    }
+}
+
+#[derive(Debug)]
 pub enum Rebuild {
     NewSeed(u64),
+    /// Rebuild with the current seed.
+    SameSeed,
+    /// Rebuild with the current seed, replaying up to the given frame and pausing there.
+    Seek(usize),
+    /// Capture the given frame at the capture scale, then rebuild with the current seed and pause
+    /// there as for `Seek`.
+    Capture(usize),
+    /// Rebuild with the seed before the current one in the seed history.
+    PreviousSeed,
+    /// Rebuild with the seed after the current one in the seed history.
+    NextSeed,
+}
+
+/// The seeds seen in a run, which can be navigated like an undo stack.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedHistory {
+    seeds: Vec<u64>,
+    position: usize,
+}
+
+impl SeedHistory {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seeds: vec![seed],
+            position: 0,
+        }
+    }
+
+    /// Returns the seed at the current position in the history.
+    pub fn current(&self) -> u64 {
+        self.seeds[self.position]
+    }
+
+    /// Adds a seed after the current one and moves to it, dropping any seeds that were after the
+    /// current one.
+    pub fn push(&mut self, seed: u64) {
+        self.seeds.truncate(self.position + 1);
+        self.seeds.push(seed);
+        self.position += 1;
+    }
+
+    /// Moves to the previous seed, if there is one.
+    pub fn back(&mut self) {
+        self.position = self.position.saturating_sub(1);
+    }
+
+    /// Moves to the next seed, if there is one.
+    pub fn forward(&mut self) {
+        self.position = (self.position + 1).min(self.seeds.len() - 1);
+    }
+}
+
+/// Displays the current seed and its position in the history, e.g. "1234 (2/3)".
+impl fmt::Display for SeedHistory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}/{})",
+            self.current(),
+            self.position + 1,
+            self.seeds.len()
+        )
+    }
 }
 
 pub struct RenderReport {
@@ -44,22 +145,111 @@ pub struct RenderReport {
 
 #[derive(Debug)]
 struct FrameUpdates {
-    new_seed: Option<u64>,
+    input: Input,
     wait: Option<Duration>,
+    draw_time: Duration,
+    output_time: Duration,
+}
+
+/// How long the stages of rendering frames have taken, for `Options::profile`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Profile {
+    /// Running the artist's paint function.
+    pub paint: Timing,
+    /// Rastering the canvas and drawing it on the GPU.
+    pub draw: Timing,
+    /// Presenting the frame to the window, or reading it back and queuing it to be saved.
+    pub output: Timing,
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Profile of {} frames:", self.paint.count)?;
+        writeln!(f, "  paint:  {}", self.paint)?;
+        writeln!(f, "  draw:   {}", self.draw)?;
+        write!(f, "  output: {}", self.output)
+    }
+}
+
+/// The fastest, mean and slowest of a series of durations, kept without storing them all.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Timing {
+    count: u32,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl Timing {
+    pub fn record(&mut self, duration: Duration) {
+        self.min = if self.count == 0 {
+            duration
+        } else {
+            self.min.min(duration)
+        };
+        self.max = self.max.max(duration);
+        self.total += duration;
+        self.count += 1;
+    }
+
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.total.checked_div(self.count).unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "min {:?}, mean {:?}, max {:?}",
+            self.min(),
+            self.mean(),
+            self.max()
+        )
+    }
+}
+
+/// Input from the user since it was last checked.
+#[derive(Debug, Default)]
+struct Input {
+    rebuild: Option<Rebuild>,
     should_quit: bool,
+    toggle_pause: bool,
+    step: Option<Step>,
+    resized: bool,
+    capture: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Step {
+    Forward,
+    Back,
 }
 
 pub enum RenderStrategy<F1, F2> {
     Screen {
         get_frame: F1,
         events_loop: EventsLoop,
-        wait: Duration,
+        /// The frame budget, or none to render as fast as possible.
+        wait: Option<Duration>,
         texture_program: Rc<Program>,
         buffer: Texture2dMultisample,
     },
     File {
         output_path: F2,
         buffer: Texture2dMultisample,
+        /// The program resolving the buffer for saving, or none to average samples with a blit.
+        resolve_program: Option<Rc<Program>>,
+        /// The encoder to stream frames into, taken when rendering finishes.
+        encoder: Option<FrameEncoder>,
     },
 }
 
@@ -68,12 +258,17 @@ pub struct Renderer<'a, F1, F2> {
     pub strategy: &'a mut RenderStrategy<F1, F2>,
     pub gpu: &'a Gpu,
     pub options: Options,
-    pub rng: &'a mut StdRng,
+    pub rng: &'a mut dyn RngCore,
     pub output_width: u32,
     pub output_height: u32,
+    /// Whether to pause after the first rendered frame.
+    pub paused: bool,
+    pub seed_history: &'a SeedHistory,
+    /// Records how long frames take to render, and is logged if `Options::profile` is set.
+    pub profile: &'a mut Profile,
 }
 
-impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> PathBuf> Renderer<'a, F1, F2> {
+impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> Result<PathBuf>> Renderer<'a, F1, F2> {
     /// Render all of the frames for the composition. This will not return until until all frames of
     /// the composition have been rendered.
     pub fn render_frames(
@@ -82,6 +277,8 @@ impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> PathBuf> Renderer<'a, F1,
     ) -> Result<RenderReport> {
         let default_shader = self.gpu.default_shader();
 
+        let mut render_times = vec![];
+
         let end_frame = self.options.world.frames.map(|f| f + self.options.delay);
         for frame in std::iter::successors(Some(0), move |last| {
             if let Some(end_frame) = end_frame {
@@ -94,36 +291,110 @@ impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> PathBuf> Renderer<'a, F1,
                 Some(last + 1)
             }
         }) {
+            let frame_start = Instant::now();
             let mut canvas = Canvas::new(default_shader.clone(), self.options.world.scale);
             f(
                 Context {
                     rng: self.rng,
                     world: self.options.world,
+                    options: &self.options,
                     frame,
                     time: Duration::from_secs_f32(
                         frame as f32 / self.options.world.framerate as f32,
                     ),
+                    render_times: &render_times,
                 },
                 &mut canvas,
             );
+            if frame < self.options.start_frame {
+                // Seeking is only possible by replaying, since artists may keep state between
+                // frames and draw from the rng each frame.
+                continue;
+            }
+            let paint_time = frame_start.elapsed();
 
             let updates = self.render_frame(self.options.world.seed, frame, canvas)?;
-            if updates.should_quit {
-                return Ok(RenderReport {
-                    explicit_quit: true,
-                    rebuild: None,
-                });
+            let render_time = frame_start.elapsed();
+            self.profile.paint.record(paint_time);
+            self.profile.draw.record(updates.draw_time);
+            self.profile.output.record(updates.output_time);
+            if self.options.profile {
+                eprintln!(
+                    "Frame {}: paint {:?}, draw {:?}, output {:?}",
+                    frame, paint_time, updates.draw_time, updates.output_time
+                );
+            }
+            if render_times.len() == RENDER_TIME_HISTORY {
+                render_times.remove(0);
             }
+            render_times.push(render_time);
 
             if let Some(wait) = updates.wait {
-                std::thread::sleep(wait);
+                // Sleep only for what remains of the budget, so frames keep to the frame rate.
+                match wait.checked_sub(render_time) {
+                    Some(remaining) => std::thread::sleep(remaining),
+                    None => eprintln!(
+                        "Dropped frame {}: rendered in {:?}, over the {:?} budget.",
+                        frame, render_time, wait
+                    ),
+                }
             }
 
-            if let Some(new_seed) = updates.new_seed {
-                return Ok(RenderReport {
-                    explicit_quit: false,
-                    rebuild: Some(Rebuild::NewSeed(new_seed)),
-                });
+            // Space toggles pause. While paused, '.' steps forward one frame, and ',' steps back
+            // one frame. Stepping back rebuilds the artist and replays every frame before the
+            // target, since artists may keep state between frames, so it gets slower the further
+            // into the painting it is. 'c' captures the frame at the capture scale, replaying the
+            // same way and then pausing on the frame.
+            let mut input = updates.input;
+            loop {
+                if input.should_quit {
+                    return Ok(RenderReport {
+                        explicit_quit: true,
+                        rebuild: None,
+                    });
+                }
+                if let Some(rebuild) = input.rebuild {
+                    return Ok(RenderReport {
+                        explicit_quit: false,
+                        rebuild: Some(rebuild),
+                    });
+                }
+                if input.capture {
+                    return Ok(RenderReport {
+                        explicit_quit: false,
+                        rebuild: Some(Rebuild::Capture(frame)),
+                    });
+                }
+                if input.resized {
+                    self.resize_buffer()?;
+                    if self.paused {
+                        // Redraw the paused frame at the new size.
+                        return Ok(RenderReport {
+                            explicit_quit: false,
+                            rebuild: Some(Rebuild::Seek(frame)),
+                        });
+                    }
+                }
+                if input.toggle_pause {
+                    self.paused = !self.paused;
+                }
+                if self.paused {
+                    match input.step {
+                        Some(Step::Forward) => break,
+                        Some(Step::Back) => {
+                            return Ok(RenderReport {
+                                explicit_quit: false,
+                                rebuild: Some(Rebuild::Seek(frame.saturating_sub(1))),
+                            })
+                        }
+                        None => {}
+                    }
+                } else {
+                    break;
+                }
+
+                std::thread::sleep(PAUSED_POLL_INTERVAL);
+                input = self.poll_input();
             }
         }
 
@@ -133,12 +404,36 @@ impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> PathBuf> Renderer<'a, F1,
         })
     }
 
+    /// Rebuilds the screen buffer to match the window's framebuffer, so frames keep rendering at
+    /// the window's resolution after it is resized.
+    fn resize_buffer(&mut self) -> Result<()> {
+        if let RenderStrategy::Screen { buffer, .. } = self.strategy {
+            let (width, height) = self.gpu.framebuffer_dimensions();
+            if (width, height) != buffer.dimensions() && width > 0 && height > 0 {
+                *buffer = self.gpu.build_texture(width, height, buffer.samples())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_input(&mut self) -> Input {
+        match self.strategy {
+            RenderStrategy::Screen { events_loop, .. } => poll_input(events_loop),
+            RenderStrategy::File { .. } => Input::default(),
+        }
+    }
+
     fn render_frame(
         &mut self,
         current_seed: u64,
         frame_number: usize,
         canvas: Canvas,
     ) -> Result<FrameUpdates> {
+        let layers = &self.options.layers;
+        let elements = canvas
+            .into_iter()
+            .filter(|element| layers.is_empty() || layers.contains(&element.layer))
+            .collect::<Vec<_>>();
         match self.strategy {
             RenderStrategy::Screen {
                 get_frame,
@@ -147,13 +442,19 @@ impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> PathBuf> Renderer<'a, F1,
                 texture_program,
                 wait,
             } => {
+                let draw_start = Instant::now();
+                if let Some(background) = self.options.background {
+                    background.clear(&mut buffer.as_surface());
+                }
                 self.gpu.render(
                     self.output_width,
                     self.output_height,
-                    canvas,
+                    elements,
                     &mut buffer.as_surface(),
                 )?;
+                let draw_time = draw_start.elapsed();
 
+                let output_start = Instant::now();
                 #[derive(UniformSet)]
                 struct QuadUniforms {
                     texture_in: Texture2dMultisample,
@@ -162,22 +463,7 @@ impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> PathBuf> Renderer<'a, F1,
                 let shader = self.gpu.build_shader(
                     texture_program.clone(),
                     QuadUniforms {
-                        texture_in: unsafe {
-                            // Create a weak reference to the intermediate buffer, which we will draw
-                            // to the frame buffer with a quad.
-                            Texture2dMultisample::from_id(
-                                self.gpu.ctx.get_context(),
-                                TEXTURE_FORMAT,
-                                buffer.get_id(),
-                                /*owned=*/ false,
-                                MipmapsOption::NoMipmap,
-                                Dimensions::Texture2dMultisample {
-                                    width: buffer.dimensions().0,
-                                    height: buffer.dimensions().1,
-                                    samples: buffer.samples(),
-                                },
-                            )
-                        },
+                        texture_in: weak_reference(self.gpu, buffer),
                     },
                 );
                 let mut quad_canvas = Canvas::new(shader.clone(), self.options.world.scale);
@@ -186,6 +472,17 @@ impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> PathBuf> Renderer<'a, F1,
                 let mut frame = get_frame();
                 frame.set_finish()?;
 
+                let frames = self
+                    .options
+                    .world
+                    .frames
+                    .map(|frames| format!("/{}", frames))
+                    .unwrap_or_default();
+                self.gpu.set_title(&format!(
+                    "valora | seed {} | frame {}{}",
+                    self.seed_history, frame_number, frames
+                ));
+
                 self.gpu.render(
                     self.output_width,
                     self.output_height,
@@ -193,76 +490,205 @@ impl<'a, F1: Fn() -> Frame + 'a, F2: Fn(usize, u64) -> PathBuf> Renderer<'a, F1,
                     &mut frame,
                 )?;
 
-                let mut new_seed = None;
-                let mut should_quit = false;
-                events_loop.poll_events(|event| {
-                    use glutin::{DeviceEvent, ElementState, Event, KeyboardInput, VirtualKeyCode};
-                    match event {
-                        Event::DeviceEvent {
-                            event:
-                                DeviceEvent::Key(KeyboardInput {
-                                    virtual_keycode: Some(VirtualKeyCode::Escape),
-                                    ..
-                                }),
-                            ..
-                        } => {
-                            should_quit = true;
-                        }
-                        Event::DeviceEvent {
-                            event:
-                                DeviceEvent::Key(KeyboardInput {
-                                    state: ElementState::Released,
-                                    virtual_keycode: Some(VirtualKeyCode::R),
-                                    ..
-                                }),
-                            ..
-                        } => {
-                            new_seed = Some(random());
-                        }
-                        _ => {}
-                    }
-                });
-
                 Ok(FrameUpdates {
-                    new_seed,
-                    wait: Some(*wait),
-                    should_quit,
+                    input: poll_input(events_loop),
+                    wait: *wait,
+                    draw_time,
+                    output_time: output_start.elapsed(),
                 })
             }
             RenderStrategy::File {
                 output_path,
                 buffer,
+                resolve_program,
+                encoder,
             } => {
+                let draw_start = Instant::now();
+                if let Some(background) = self.options.background {
+                    background.clear(&mut buffer.as_surface());
+                }
                 self.gpu.render(
                     self.output_width,
                     self.output_height,
-                    canvas,
+                    elements,
                     &mut buffer.as_surface(),
                 )?;
+                let draw_time = draw_start.elapsed();
 
+                let output_start = Instant::now();
                 if frame_number > self.options.delay {
-                    let raw: glium::texture::RawImage2d<u8> = self.gpu.read_to_ram(&buffer)?;
-                    let image: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::from_raw(
-                        self.output_width,
-                        self.output_height,
-                        raw.data
-                            .into_par_iter()
-                            .map(|v| v.convert::<f32>())
-                            .map(|v: f32| <Srgb as TransferFn>::from_linear(v))
-                            .map(|v| v.convert::<u8>())
-                            .collect(),
-                    )
-                    .unwrap();
-
-                    image.save(output_path(frame_number, current_seed))?;
+                    let image = match resolve_program {
+                        Some(program) => {
+                            #[derive(UniformSet)]
+                            struct ResolveUniforms {
+                                texture_in: Texture2dMultisample,
+                                samples: i32,
+                            }
+
+                            let shader = self.gpu.build_shader(
+                                program.clone(),
+                                ResolveUniforms {
+                                    texture_in: weak_reference(self.gpu, buffer),
+                                    samples: buffer.samples() as i32,
+                                },
+                            );
+                            // The resolved pixels replace the target's, so the frame's alpha
+                            // comes through unchanged rather than blended over stale memory.
+                            let mut quad_canvas = Canvas::new(shader, self.options.world.scale);
+                            quad_canvas.set_blend_mode(BlendMode::Replace);
+                            quad_canvas.paint(Filled(self.options.world));
+
+                            let target = self
+                                .gpu
+                                .build_ram_texture(self.output_width, self.output_height)?;
+                            target.as_surface().clear_color(0., 0., 0., 0.);
+                            self.gpu.render(
+                                self.output_width,
+                                self.output_height,
+                                quad_canvas,
+                                &mut target.as_surface(),
+                            )?;
+                            encode_image(target.read())
+                        }
+                        None => self.gpu.read_image(&buffer)?,
+                    };
+
+                    if let Some(encoder) = encoder {
+                        encoder.write_frame(image, || output_path(frame_number, current_seed))?;
+                    }
                 }
 
                 Ok(FrameUpdates {
-                    new_seed: None,
+                    input: Input::default(),
                     wait: None,
-                    should_quit: false,
+                    draw_time,
+                    output_time: output_start.elapsed(),
                 })
             }
         }
     }
 }
+
+fn poll_input(events_loop: &mut EventsLoop) -> Input {
+    let mut input = Input::default();
+    events_loop.poll_events(|event| {
+        use glutin::{
+            DeviceEvent, ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent,
+        };
+        match event {
+            Event::DeviceEvent {
+                event:
+                    DeviceEvent::Key(KeyboardInput {
+                        virtual_keycode: Some(VirtualKeyCode::Escape),
+                        ..
+                    }),
+                ..
+            } => {
+                input.should_quit = true;
+            }
+            Event::DeviceEvent {
+                event:
+                    DeviceEvent::Key(KeyboardInput {
+                        state: ElementState::Released,
+                        virtual_keycode: Some(VirtualKeyCode::R),
+                        modifiers,
+                        ..
+                    }),
+                ..
+            } => {
+                input.rebuild = Some(if modifiers.shift {
+                    Rebuild::SameSeed
+                } else {
+                    Rebuild::NewSeed(random())
+                });
+            }
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => {
+                input.should_quit = true;
+            }
+            Event::WindowEvent {
+                event: WindowEvent::Resized(_),
+                ..
+            } => {
+                input.resized = true;
+            }
+            Event::WindowEvent {
+                event: WindowEvent::ReceivedCharacter(c),
+                ..
+            } => match c {
+                '[' => input.rebuild = Some(Rebuild::PreviousSeed),
+                ']' => input.rebuild = Some(Rebuild::NextSeed),
+                ' ' => input.toggle_pause = true,
+                '.' => input.step = Some(Step::Forward),
+                ',' => input.step = Some(Step::Back),
+                'c' => input.capture = true,
+                _ => {}
+            },
+            _ => {}
+        }
+    });
+    input
+}
+
+/// Creates a weak reference to an intermediate buffer, which can be drawn to another surface with
+/// a quad.
+fn weak_reference(gpu: &Gpu, buffer: &Texture2dMultisample) -> Texture2dMultisample {
+    unsafe {
+        Texture2dMultisample::from_id(
+            gpu.ctx.get_context(),
+            TEXTURE_FORMAT,
+            buffer.get_id(),
+            /*owned=*/ false,
+            MipmapsOption::NoMipmap,
+            Dimensions::Texture2dMultisample {
+                width: buffer.dimensions().0,
+                height: buffer.dimensions().1,
+                samples: buffer.samples(),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seed_history() {
+        let mut history = SeedHistory::new(1);
+        history.back();
+        assert_eq!(history.current(), 1);
+
+        history.push(2);
+        history.push(3);
+        assert_eq!(history.to_string(), "3 (3/3)");
+        history.forward();
+        assert_eq!(history.current(), 3);
+
+        history.back();
+        history.back();
+        assert_eq!(history.to_string(), "1 (1/3)");
+        history.forward();
+        assert_eq!(history.current(), 2);
+
+        history.push(4);
+        assert_eq!(history.to_string(), "4 (3/3)");
+        history.back();
+        assert_eq!(history.current(), 2);
+    }
+
+    #[test]
+    fn timing() {
+        let mut timing = Timing::default();
+        assert_eq!(timing.mean(), Duration::from_millis(0));
+        for ms in &[4, 1, 7] {
+            timing.record(Duration::from_millis(*ms));
+        }
+        assert_eq!(timing.min(), Duration::from_millis(1));
+        assert_eq!(timing.mean(), Duration::from_millis(4));
+        assert_eq!(timing.max(), Duration::from_millis(7));
+        assert_eq!(timing.to_string(), "min 1ms, mean 4ms, max 7ms");
+    }
+}
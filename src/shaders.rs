@@ -1,14 +1,34 @@
 //! Fragment shader API.
 
 use crate::{
-    gpu::{Gpu, Shader},
+    gpu::{Gpu, Shader, UniformBuffer},
     uniforms::OwnedUniforms,
-    Result,
+    Result, World, P2,
 };
-use glium::Program;
+use failure::format_err;
+use glium::{uniforms::UniformValue, Program};
 use glslwatch::GLSLTree;
+use palette::LinSrgba;
 use std::{path::Path, rc::Rc};
 
+/// The most color stops a gradient shader can have.
+pub const MAX_GRADIENT_STOPS: usize = 16;
+
+/// The most octaves a noise shader can sum.
+pub const MAX_NOISE_OCTAVES: u32 = 8;
+
+/// Builds the source of a built-in shader which colors by the shared stop `gradient()`.
+macro_rules! gradient_shader {
+    ($path:literal) => {
+        concat!(
+            "#version 400\n\n",
+            include_str!("shaders/gradient.glsl"),
+            "\n",
+            include_str!($path)
+        )
+    };
+}
+
 /// A dynamically reloaded GLSL program.
 pub struct ShaderProgram {
     tree: GLSLTree,
@@ -71,3 +91,340 @@ impl ShaderProgram {
         Ok(self.last_shader.clone())
     }
 }
+
+/// A built-in shader which colors paths with a linear gradient, in place of the canvas color.
+///
+/// The gradient is laid out in world coordinates, so it stays put under canvas transforms. The
+/// canvas alpha still fades it.
+pub struct LinearGradient {
+    gpu: Gpu,
+    program: Rc<Program>,
+    scale: f32,
+}
+
+impl LinearGradient {
+    /// Compiles the gradient shader for painting in the given world.
+    pub fn new(gpu: &Gpu, world: World) -> Result<Self> {
+        Ok(Self {
+            gpu: gpu.clone(),
+            program: gpu.compile_glsl(gradient_shader!("shaders/linear_gradient.frag"))?,
+            scale: world.scale,
+        })
+    }
+
+    /// Returns a shader for the gradient from `start` to `end` through the given stops.
+    ///
+    /// Each stop pairs a position along the gradient, where 0 is at `start` and 1 at `end`, with
+    /// a color. Stops must be in order of position, or binding fails; two stops at one position
+    /// make a hard edge. Points before the first stop or after the last take its color.
+    pub fn bind(&self, stops: &[(f32, LinSrgba)], start: P2, end: P2) -> Result<Shader> {
+        let mut uniforms = stop_uniforms(stops)?;
+        uniforms.push(
+            String::from("start"),
+            UniformValue::Vec2((start * self.scale).to_array()),
+        );
+        uniforms.push(
+            String::from("end"),
+            UniformValue::Vec2((end * self.scale).to_array()),
+        );
+        Ok(self.gpu.build_shader(self.program.clone(), uniforms))
+    }
+}
+
+/// A built-in shader which colors paths with a radial gradient, in place of the canvas color.
+///
+/// The gradient is laid out in world coordinates, so it stays put under canvas transforms. The
+/// canvas alpha still fades it.
+pub struct RadialGradient {
+    gpu: Gpu,
+    program: Rc<Program>,
+    scale: f32,
+}
+
+impl RadialGradient {
+    /// Compiles the gradient shader for painting in the given world.
+    pub fn new(gpu: &Gpu, world: World) -> Result<Self> {
+        Ok(Self {
+            gpu: gpu.clone(),
+            program: gpu.compile_glsl(gradient_shader!("shaders/radial_gradient.frag"))?,
+            scale: world.scale,
+        })
+    }
+
+    /// Returns a shader for the gradient out from `center` to `radius` through the given stops.
+    ///
+    /// Each stop pairs a distance from the center, as a fraction of the radius, with a color.
+    /// Stops must be in order of distance, or binding fails. Points nearer than the first stop or
+    /// further than the last take its color.
+    pub fn bind(&self, stops: &[(f32, LinSrgba)], center: P2, radius: f32) -> Result<Shader> {
+        let uniforms = radial_uniforms(stops, center * self.scale, radius * self.scale)?;
+        Ok(self.gpu.build_shader(self.program.clone(), uniforms))
+    }
+}
+
+/// The kind of noise a `NoiseShader` draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    /// Random values at lattice points, smoothly interpolated. Blocky, but cheapest.
+    Value,
+    /// Gradient noise over a cubic lattice.
+    Perlin,
+    /// Gradient noise over a simplex lattice, with fewer directional artifacts than Perlin.
+    Simplex,
+}
+
+/// The noise a `NoiseShader` draws.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseField {
+    pub kind: NoiseKind,
+    /// The width in world units of the coarsest octave's features.
+    pub scale: f32,
+    /// How many octaves of noise to sum, each at twice the frequency and half the amplitude of
+    /// the last, from 1 to `MAX_NOISE_OCTAVES`.
+    pub octaves: u32,
+    /// Selects one of many unrelated fields.
+    pub seed: u32,
+    /// Where along its third axis the field is sliced. Passing the elapsed time, in seconds,
+    /// makes the noise flow as frames go by.
+    pub time: f32,
+}
+
+/// A built-in shader which colors paths with fractal noise mapped through a color ramp, in place
+/// of the canvas color.
+///
+/// The noise is laid out in world coordinates, so it stays put under canvas transforms. The
+/// canvas alpha still fades it.
+pub struct NoiseShader {
+    gpu: Gpu,
+    program: Rc<Program>,
+    scale: f32,
+}
+
+impl NoiseShader {
+    /// Compiles the noise shader for painting in the given world.
+    pub fn new(gpu: &Gpu, world: World) -> Result<Self> {
+        Ok(Self {
+            gpu: gpu.clone(),
+            program: gpu.compile_glsl(gradient_shader!("shaders/noise.frag"))?,
+            scale: world.scale,
+        })
+    }
+
+    /// Returns a shader for the noise field, colored by the given stops.
+    ///
+    /// Noise values from -1 to 1 are mapped to positions 0 to 1 along the ramp. Each stop pairs a
+    /// position with a color, as for gradients.
+    pub fn bind(&self, field: NoiseField, stops: &[(f32, LinSrgba)]) -> Result<Shader> {
+        let uniforms = noise_uniforms(field, stops, self.scale)?;
+        Ok(self.gpu.build_shader(self.program.clone(), uniforms))
+    }
+}
+
+fn noise_uniforms(
+    field: NoiseField,
+    stops: &[(f32, LinSrgba)],
+    scale: f32,
+) -> Result<UniformBuffer> {
+    if field.scale <= 0. {
+        return Err(format_err!(
+            "Noise fields must have a positive scale; got {}",
+            field.scale
+        ));
+    }
+    if field.octaves == 0 || field.octaves > MAX_NOISE_OCTAVES {
+        return Err(format_err!(
+            "Noise fields must have between 1 and {} octaves; got {}",
+            MAX_NOISE_OCTAVES,
+            field.octaves
+        ));
+    }
+    let kind = match field.kind {
+        NoiseKind::Value => 0,
+        NoiseKind::Perlin => 1,
+        NoiseKind::Simplex => 2,
+    };
+    let mut uniforms = stop_uniforms(stops)?;
+    uniforms.push(String::from("kind"), UniformValue::SignedInt(kind));
+    uniforms.push(
+        String::from("frequency"),
+        UniformValue::Float(1. / (field.scale * scale)),
+    );
+    uniforms.push(
+        String::from("octaves"),
+        UniformValue::SignedInt(field.octaves as i32),
+    );
+    uniforms.push(String::from("seed"), UniformValue::UnsignedInt(field.seed));
+    uniforms.push(String::from("time"), UniformValue::Float(field.time));
+    Ok(uniforms)
+}
+
+fn radial_uniforms(stops: &[(f32, LinSrgba)], center: P2, radius: f32) -> Result<UniformBuffer> {
+    if radius <= 0. {
+        return Err(format_err!(
+            "Radial gradients must have a positive radius; got {}",
+            radius
+        ));
+    }
+    let mut uniforms = stop_uniforms(stops)?;
+    uniforms.push(
+        String::from("center"),
+        UniformValue::Vec2(center.to_array()),
+    );
+    uniforms.push(String::from("radius"), UniformValue::Float(radius));
+    Ok(uniforms)
+}
+
+fn stop_uniforms(stops: &[(f32, LinSrgba)]) -> Result<UniformBuffer> {
+    if stops.is_empty() || stops.len() > MAX_GRADIENT_STOPS {
+        return Err(format_err!(
+            "Gradients must have between 1 and {} stops; got {}",
+            MAX_GRADIENT_STOPS,
+            stops.len()
+        ));
+    }
+    if stops.iter().any(|(position, _)| !position.is_finite()) {
+        return Err(format_err!("Gradient stop positions must be finite"));
+    }
+    if stops.windows(2).any(|pair| pair[1].0 < pair[0].0) {
+        return Err(format_err!(
+            "Gradient stop positions must not decrease; got {:?}",
+            stops
+                .iter()
+                .map(|(position, _)| *position)
+                .collect::<Vec<_>>()
+        ));
+    }
+
+    let mut uniforms = UniformBuffer::default();
+    uniforms.push(
+        String::from("stop_count"),
+        UniformValue::SignedInt(stops.len() as i32),
+    );
+    for (i, (position, color)) in stops.iter().enumerate() {
+        uniforms.push(
+            format!("stop_positions[{}]", i),
+            UniformValue::Float(*position),
+        );
+        uniforms.push(
+            format!("stop_colors[{}]", i),
+            UniformValue::Vec4([
+                color.color.red,
+                color.color.green,
+                color.color.blue,
+                color.alpha,
+            ]),
+        );
+    }
+    Ok(uniforms)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use glium::uniforms::Uniforms;
+    use palette::{Alpha, LinSrgb};
+
+    #[test]
+    fn gradient_stop_uniforms() {
+        let red = Alpha::<LinSrgb, _>::new(1., 0., 0., 1.);
+        let blue = Alpha::<LinSrgb, _>::new(0., 0., 1., 0.5);
+        let uniforms = stop_uniforms(&[(0., red), (0.75, blue)]).unwrap();
+
+        let mut names = vec![];
+        uniforms.visit_values(|name, value| {
+            match (name, value) {
+                ("stop_count", UniformValue::SignedInt(2)) => {}
+                ("stop_positions[1]", UniformValue::Float(p)) => assert_eq!(p, 0.75),
+                ("stop_colors[1]", UniformValue::Vec4(c)) => assert_eq!(c, [0., 0., 1., 0.5]),
+                ("stop_positions[0]", _) | ("stop_colors[0]", _) => {}
+                _ => panic!("Unexpected uniform {}", name),
+            }
+            names.push(name.to_string());
+        });
+        assert_eq!(names.len(), 5);
+
+        assert!(stop_uniforms(&[]).is_err());
+        assert!(stop_uniforms(&vec![(0., red); MAX_GRADIENT_STOPS + 1]).is_err());
+        assert!(stop_uniforms(&[(0.5, red), (0.25, blue)]).is_err());
+        assert!(stop_uniforms(&[(0., red), (f32::NAN, blue)]).is_err());
+        assert!(stop_uniforms(&[(0., red), (0.5, blue), (0.5, red)]).is_ok());
+    }
+
+    #[test]
+    fn gradient_shaders_share_one_ramp() {
+        for source in &[
+            gradient_shader!("shaders/linear_gradient.frag"),
+            gradient_shader!("shaders/radial_gradient.frag"),
+            gradient_shader!("shaders/noise.frag"),
+        ] {
+            assert!(source.starts_with("#version 400\n"));
+            assert_eq!(source.matches("#version").count(), 1);
+            assert_eq!(source.matches("vec4 gradient(float t)").count(), 1);
+        }
+    }
+
+    #[test]
+    fn radial_gradient_uniforms() {
+        let red = Alpha::<LinSrgb, _>::new(1., 0., 0., 1.);
+        let center = P2::new(10., 20.);
+        for stops in &[vec![(0., red)], vec![(0., red); MAX_GRADIENT_STOPS]] {
+            let uniforms = radial_uniforms(stops, center, 5.).unwrap();
+            let mut count = 0;
+            uniforms.visit_values(|name, value| {
+                match (name, value) {
+                    ("center", UniformValue::Vec2(c)) => assert_eq!(c, [10., 20.]),
+                    ("radius", UniformValue::Float(r)) => assert_eq!(r, 5.),
+                    _ => {}
+                }
+                count += 1;
+            });
+            assert_eq!(count, 3 + stops.len() * 2);
+        }
+
+        assert!(radial_uniforms(&[(0., red)], center, 0.).is_err());
+        assert!(radial_uniforms(&[], center, 5.).is_err());
+    }
+
+    #[test]
+    fn noise_field_uniforms() {
+        let red = Alpha::<LinSrgb, _>::new(1., 0., 0., 1.);
+        let field = NoiseField {
+            kind: NoiseKind::Simplex,
+            scale: 50.,
+            octaves: 4,
+            seed: 7,
+            time: 1.5,
+        };
+        let uniforms = noise_uniforms(field, &[(0., red)], 2.).unwrap();
+        let mut count = 0;
+        uniforms.visit_values(|name, value| {
+            match (name, value) {
+                ("kind", UniformValue::SignedInt(k)) => assert_eq!(k, 2),
+                ("frequency", UniformValue::Float(f)) => assert_eq!(f, 0.01),
+                ("octaves", UniformValue::SignedInt(o)) => assert_eq!(o, 4),
+                ("seed", UniformValue::UnsignedInt(s)) => assert_eq!(s, 7),
+                ("time", UniformValue::Float(t)) => assert_eq!(t, 1.5),
+                ("stop_count", _) | ("stop_positions[0]", _) | ("stop_colors[0]", _) => {}
+                _ => panic!("Unexpected uniform {}", name),
+            }
+            count += 1;
+        });
+        assert_eq!(count, 8);
+
+        let invalid = [
+            NoiseField { scale: 0., ..field },
+            NoiseField {
+                octaves: 0,
+                ..field
+            },
+            NoiseField {
+                octaves: MAX_NOISE_OCTAVES + 1,
+                ..field
+            },
+        ];
+        for field in &invalid {
+            assert!(noise_uniforms(*field, &[(0., red)], 2.).is_err());
+        }
+        assert!(noise_uniforms(field, &[], 2.).is_err());
+    }
+}
@@ -6,8 +6,11 @@ uniform float _valora_height_sign;
 
 in vec2 vpos;
 in vec4 vcol;
+in vec4 vdata;
 
 out vec4 v_color;
+out vec4 v_data;
+out vec2 v_position;
 
 vec2 _project_coordinates() {
   vec2 tmp = vpos;
@@ -18,4 +21,6 @@ vec2 _project_coordinates() {
 void main() {
   gl_Position = vec4(_project_coordinates(), 0., 1.);
   v_color = vcol;
+  v_data = vdata;
+  v_position = vpos;
 }
\ No newline at end of file
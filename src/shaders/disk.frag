@@ -0,0 +1,13 @@
+#version 400
+
+in vec4 v_color;
+in float v_radius;
+
+out vec4 frag;
+
+void main() {
+  vec2 p = (gl_PointCoord * 2. - 1.) * (v_radius + 1.);
+  float coverage = clamp(v_radius - length(p) + 0.5, 0., 1.);
+  vec4 color = clamp(v_color, 0., 1.);
+  frag = vec4(color.rgb, color.a * coverage);
+}
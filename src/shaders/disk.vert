@@ -0,0 +1,26 @@
+#version 400
+
+uniform float _valora_width;
+uniform float _valora_height;
+uniform float _valora_height_sign;
+
+in vec2 vpos;
+in float vradius;
+in vec4 vcol;
+
+out vec4 v_color;
+out float v_radius;
+
+vec2 _project_coordinates() {
+  vec2 tmp = vpos;
+  tmp.y = _valora_height - tmp.y;
+  return vec2(tmp.x / _valora_width * 2. - 1., _valora_height_sign * (tmp.y / _valora_height * 2. - 1.));
+}
+
+void main() {
+  gl_Position = vec4(_project_coordinates(), 0., 1.);
+  // Pad the sprite by a pixel on each side so the antialiased edge is not clipped.
+  gl_PointSize = vradius * 2. + 2.;
+  v_color = vcol;
+  v_radius = vradius;
+}
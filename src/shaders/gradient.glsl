@@ -0,0 +1,20 @@
+const int MAX_STOPS = 16;
+
+uniform float stop_positions[MAX_STOPS];
+uniform vec4 stop_colors[MAX_STOPS];
+uniform int stop_count;
+
+// Returns the color of the stops at `t`, which take the color of the nearest stop outside them.
+vec4 gradient(float t) {
+  if (t <= stop_positions[0]) {
+    return stop_colors[0];
+  }
+  for (int i = 1; i < stop_count; ++i) {
+    if (t < stop_positions[i]) {
+      float span = stop_positions[i] - stop_positions[i - 1];
+      float s = span > 0. ? (t - stop_positions[i - 1]) / span : 1.;
+      return mix(stop_colors[i - 1], stop_colors[i], s);
+    }
+  }
+  return stop_colors[stop_count - 1];
+}
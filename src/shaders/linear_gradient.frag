@@ -0,0 +1,15 @@
+uniform vec2 start;
+uniform vec2 end;
+
+in vec4 v_color;
+in vec2 v_position;
+
+out vec4 frag;
+
+void main() {
+  vec2 axis = end - start;
+  float length_squared = dot(axis, axis);
+  float t = length_squared > 0. ? dot(v_position - start, axis) / length_squared : stop_positions[0];
+  vec4 color = clamp(gradient(t), 0., 1.);
+  frag = vec4(color.rgb, color.a * v_color.a);
+}
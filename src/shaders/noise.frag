@@ -0,0 +1,116 @@
+const int VALUE = 0;
+const int PERLIN = 1;
+const int SIMPLEX = 2;
+
+uniform int kind;
+uniform float frequency;
+uniform int octaves;
+uniform uint seed;
+uniform float time;
+
+in vec4 v_color;
+in vec2 v_position;
+
+out vec4 frag;
+
+uint hash(ivec3 cell) {
+  uvec3 v = uvec3(cell) * 1664525u + 1013904223u + seed * 2654435769u;
+  v.x += v.y * v.z;
+  v.y += v.z * v.x;
+  v.z += v.x * v.y;
+  v ^= v >> 16u;
+  v.x += v.y * v.z;
+  v.y += v.z * v.x;
+  v.z += v.x * v.y;
+  return v.x ^ v.y ^ v.z;
+}
+
+float lattice_value(ivec3 cell) {
+  return float(hash(cell)) / 4294967295. * 2. - 1.;
+}
+
+vec3 lattice_gradient(ivec3 cell) {
+  uint h = hash(cell);
+  vec3 g = vec3(h & 0x3FFu, (h >> 10u) & 0x3FFu, (h >> 20u) & 0x3FFu) / 511.5 - 1.;
+  float len = length(g);
+  return len > 0. ? g / len : vec3(1., 0., 0.);
+}
+
+float value_noise(vec3 p) {
+  ivec3 i = ivec3(floor(p));
+  vec3 f = fract(p);
+  vec3 u = f * f * (3. - 2. * f);
+  return mix(
+      mix(mix(lattice_value(i), lattice_value(i + ivec3(1, 0, 0)), u.x),
+          mix(lattice_value(i + ivec3(0, 1, 0)), lattice_value(i + ivec3(1, 1, 0)), u.x), u.y),
+      mix(mix(lattice_value(i + ivec3(0, 0, 1)), lattice_value(i + ivec3(1, 0, 1)), u.x),
+          mix(lattice_value(i + ivec3(0, 1, 1)), lattice_value(i + ivec3(1, 1, 1)), u.x), u.y),
+      u.z);
+}
+
+float corner(ivec3 i, vec3 f, ivec3 offset) {
+  return dot(lattice_gradient(i + offset), f - vec3(offset));
+}
+
+float perlin_noise(vec3 p) {
+  ivec3 i = ivec3(floor(p));
+  vec3 f = fract(p);
+  vec3 u = f * f * f * (f * (f * 6. - 15.) + 10.);
+  return mix(
+      mix(mix(corner(i, f, ivec3(0, 0, 0)), corner(i, f, ivec3(1, 0, 0)), u.x),
+          mix(corner(i, f, ivec3(0, 1, 0)), corner(i, f, ivec3(1, 1, 0)), u.x), u.y),
+      mix(mix(corner(i, f, ivec3(0, 0, 1)), corner(i, f, ivec3(1, 0, 1)), u.x),
+          mix(corner(i, f, ivec3(0, 1, 1)), corner(i, f, ivec3(1, 1, 1)), u.x), u.y),
+      u.z);
+}
+
+float simplex_noise(vec3 p) {
+  const float F3 = 1. / 3.;
+  const float G3 = 1. / 6.;
+  vec3 s = floor(p + dot(p, vec3(F3)));
+  vec3 x = p - s + dot(s, vec3(G3));
+  vec3 e = step(vec3(0.), x - x.yzx);
+  vec3 i1 = e * (1. - e.zxy);
+  vec3 i2 = 1. - e.zxy * (1. - e);
+  vec3 x1 = x - i1 + G3;
+  vec3 x2 = x - i2 + 2. * G3;
+  vec3 x3 = x - 1. + 3. * G3;
+  ivec3 c = ivec3(s);
+  vec4 w = max(0.6 - vec4(dot(x, x), dot(x1, x1), dot(x2, x2), dot(x3, x3)), 0.);
+  vec4 d = vec4(dot(lattice_gradient(c), x),
+                dot(lattice_gradient(c + ivec3(i1)), x1),
+                dot(lattice_gradient(c + ivec3(i2)), x2),
+                dot(lattice_gradient(c + 1), x3));
+  w *= w;
+  w *= w;
+  return 32. * dot(w, d);
+}
+
+float noise(vec3 p) {
+  if (kind == PERLIN) {
+    return perlin_noise(p);
+  } else if (kind == SIMPLEX) {
+    return simplex_noise(p);
+  }
+  return value_noise(p);
+}
+
+// Sums octaves of ever finer and fainter noise, normalized to stay within [-1, 1].
+float fractal(vec3 p) {
+  float sum = 0.;
+  float amplitude = 1.;
+  float total = 0.;
+  for (int i = 0; i < octaves; ++i) {
+    sum += noise(p) * amplitude;
+    total += amplitude;
+    amplitude *= 0.5;
+    p = p * 2. + 17.;
+  }
+  return sum / total;
+}
+
+void main() {
+  float n = fractal(vec3(v_position * frequency, time));
+  vec4 color = clamp(gradient(n * 0.5 + 0.5), 0., 1.);
+  frag = vec4(color.rgb, color.a * v_color.a);
+}
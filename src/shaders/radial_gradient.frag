@@ -0,0 +1,12 @@
+uniform vec2 center;
+uniform float radius;
+
+in vec4 v_color;
+in vec2 v_position;
+
+out vec4 frag;
+
+void main() {
+  vec4 color = clamp(gradient(distance(v_position, center) / radius), 0., 1.);
+  frag = vec4(color.rgb, color.a * v_color.a);
+}
@@ -0,0 +1,25 @@
+#version 400
+
+out vec4 frag;
+
+uniform sampler2DMS texture_in;
+uniform int samples;
+
+// Resolves each pixel from the samples of its 3x3 neighborhood, weighted by a tent filter.
+void main() {
+  ivec2 texel = ivec2(floor(gl_FragCoord.x), floor(gl_FragCoord.y));
+  ivec2 size = textureSize(texture_in);
+  vec4 sum = vec4(0.);
+  float total = 0.;
+  for (int dy = -1; dy <= 1; dy++) {
+    for (int dx = -1; dx <= 1; dx++) {
+      ivec2 neighbor = clamp(texel + ivec2(dx, dy), ivec2(0), size - 1);
+      float weight = (2. - abs(float(dx))) * (2. - abs(float(dy)));
+      for (int s = 0; s < samples; s++) {
+        sum += weight * texelFetch(texture_in, neighbor, s);
+      }
+      total += weight * float(samples);
+    }
+  }
+  frag = sum / total;
+}
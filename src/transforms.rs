@@ -1,8 +1,11 @@
 //! Changes of form.
 
 mod rotate;
+mod roughen;
 mod scale;
+mod stack;
 mod subdivide;
 mod translate;
+mod warp;
 
-pub use self::{rotate::*, scale::*, subdivide::*, translate::*};
+pub use self::{rotate::*, roughen::*, scale::*, stack::*, subdivide::*, translate::*, warp::*};
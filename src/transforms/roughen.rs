@@ -0,0 +1,124 @@
+//! Hand drawn roughening.
+
+use crate::{Polygon, Polyline, P2, PI, V2};
+use itertools::Itertools;
+use rand::Rng;
+
+/// A trait for types which can be jittered to look like a shaky hand drew them.
+pub trait Roughen {
+    /// Splits each edge into pieces no longer than `segment_length` and moves each new
+    /// intermediate vertex a random distance of at most `max_offset`. Original vertices are kept.
+    ///
+    /// A `segment_length` which is not positive and finite leaves the shape unchanged.
+    fn roughen<R: Rng + ?Sized>(self, max_offset: f32, segment_length: f32, rng: &mut R) -> Self;
+}
+
+/// Returns the jittered intermediate points of the edge from `a` to `b`, excluding both ends.
+fn rough_edge<'a, R: Rng + ?Sized>(
+    a: P2,
+    b: P2,
+    max_offset: f32,
+    segment_length: f32,
+    rng: &'a mut R,
+) -> impl Iterator<Item = P2> + 'a {
+    let pieces = if segment_length > 0. && segment_length.is_finite() {
+        ((b - a).length() / segment_length).ceil().max(1.) as usize
+    } else {
+        1
+    };
+    (1..pieces).map(move |i| {
+        let theta = rng.gen_range(0., PI * 2.);
+        let r = max_offset * rng.gen_range(0f32, 1.).sqrt();
+        a.lerp(b, i as f32 / pieces as f32) + V2::new(theta.cos(), theta.sin()) * r
+    })
+}
+
+impl Roughen for Polyline {
+    fn roughen<R: Rng + ?Sized>(self, max_offset: f32, segment_length: f32, rng: &mut R) -> Self {
+        let mut vertices = self.vertices().take(1).collect::<Vec<_>>();
+        for (a, b) in self.vertices().tuple_windows() {
+            vertices.extend(rough_edge(a, b, max_offset, segment_length, rng));
+            vertices.push(b);
+        }
+        vertices.into_iter().collect()
+    }
+}
+
+impl Roughen for Polygon {
+    fn roughen<R: Rng + ?Sized>(self, max_offset: f32, segment_length: f32, rng: &mut R) -> Self {
+        let mut vertices = vec![];
+        let closing = self
+            .vertices()
+            .last()
+            .into_iter()
+            .zip(self.vertices().take(1));
+        for (a, b) in self.vertices().tuple_windows().chain(closing) {
+            vertices.push(a);
+            vertices.extend(rough_edge(a, b, max_offset, segment_length, rng));
+        }
+        vertices.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn rough_line() {
+        let (start, end) = (P2::new(0., 0.), P2::new(100., 0.));
+        let line = vec![start, end].into_iter().collect::<Polyline>();
+        let rough = line.roughen(2., 10., &mut StdRng::seed_from_u64(0));
+        let vertices = rough.vertices().collect::<Vec<_>>();
+
+        assert_eq!(vertices.len(), 11);
+        assert_eq!(vertices.first(), Some(&start));
+        assert_eq!(vertices.last(), Some(&end));
+        assert!(vertices
+            .iter()
+            .enumerate()
+            .all(|(i, p)| (*p - P2::new(i as f32 * 10., 0.)).length() <= 2.));
+        assert!(vertices.iter().any(|p| p.y != 0.));
+    }
+
+    #[test]
+    fn invalid_segment_lengths_leave_shapes_unchanged() {
+        let line = vec![P2::new(0., 0.), P2::new(100., 0.)]
+            .into_iter()
+            .collect::<Polyline>();
+        let square = vec![
+            P2::new(0., 0.),
+            P2::new(10., 0.),
+            P2::new(10., 10.),
+            P2::new(0., 10.),
+        ]
+        .into_iter()
+        .collect::<Polygon>();
+        let mut rng = StdRng::seed_from_u64(0);
+        for segment_length in &[0., -1., f32::NAN, f32::INFINITY] {
+            assert_eq!(line.clone().roughen(1., *segment_length, &mut rng), line);
+            assert_eq!(
+                square.clone().roughen(1., *segment_length, &mut rng),
+                square
+            );
+        }
+    }
+
+    #[test]
+    fn shapes_roughen_from_any_rng() {
+        let square = vec![
+            P2::new(0., 0.),
+            P2::new(10., 0.),
+            P2::new(10., 10.),
+            P2::new(0., 10.),
+        ]
+        .into_iter()
+        .collect::<Polygon>();
+        let rng: &mut dyn rand::RngCore = &mut StdRng::seed_from_u64(0);
+        assert_eq!(
+            square.clone().roughen(1., 2., rng),
+            square.roughen(1., 2., &mut StdRng::seed_from_u64(0))
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Transform stacks.
+
+use crate::{Angle, P2, V2};
+use euclid::{Transform2D, UnknownUnit};
+
+/// A two dimensional affine transform.
+pub type Transform = Transform2D<f32, UnknownUnit, UnknownUnit>;
+
+/// A stack of transforms for drawing in nested coordinate systems.
+///
+/// Each operation transforms the coordinate system that subsequent points are given in, so a
+/// `translate` followed by a `rotate` rotates about the translated origin. `push` saves the current
+/// transform and `pop` restores it, which is handy for recursive structures like trees and tilings.
+#[derive(Debug, Clone)]
+pub struct TransformStack {
+    current: Transform,
+    saved: Vec<Transform>,
+}
+
+impl Default for TransformStack {
+    fn default() -> Self {
+        Self {
+            current: Transform::identity(),
+            saved: vec![],
+        }
+    }
+}
+
+impl TransformStack {
+    /// Saves the current transform so it can be restored with `pop`.
+    pub fn push(&mut self) {
+        self.saved.push(self.current);
+    }
+
+    /// Restores the transform saved by the last unmatched `push`. If there is none, the transform
+    /// is reset to the identity.
+    pub fn pop(&mut self) {
+        self.current = self.saved.pop().unwrap_or_else(Transform::identity);
+    }
+
+    /// Translates the coordinate system.
+    pub fn translate(&mut self, translation: V2) {
+        self.current = self.current.pre_translate(translation);
+    }
+
+    /// Rotates the coordinate system about its origin.
+    ///
+    /// Rotation direction matches `Rotate` for points.
+    pub fn rotate(&mut self, theta: Angle) {
+        let (sin, cos) = theta.radians.sin_cos();
+        self.current = self
+            .current
+            .pre_transform(&Transform::row_major(cos, sin, -sin, cos, 0., 0.));
+    }
+
+    /// Scales the coordinate system about its origin.
+    pub fn scale(&mut self, factor: f32) {
+        self.current = self.current.pre_scale(factor, factor);
+    }
+
+    /// Returns the current transform.
+    pub fn transform(&self) -> Transform {
+        self.current
+    }
+
+    /// Returns the rotation applied by the current transform.
+    pub fn rotation(&self) -> Angle {
+        Angle::radians(self.current.m12.atan2(self.current.m11))
+    }
+
+    /// Returns the scale factor applied by the current transform.
+    pub fn scale_factor(&self) -> f32 {
+        self.current.m11.hypot(self.current.m12)
+    }
+
+    /// Maps a point from the current coordinate system to the root coordinate system.
+    pub fn apply(&self, p: P2) -> P2 {
+        self.current.transform_point(p)
+    }
+
+    /// Maps a vector from the current coordinate system to the root coordinate system.
+    pub fn apply_vector(&self, v: V2) -> V2 {
+        self.current.transform_vector(v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Rotate, PI};
+
+    fn assert_near(a: P2, b: P2) {
+        assert!((a - b).length() < 0.0001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn push_translate_pop() {
+        let mut stack = TransformStack::default();
+        stack.translate(V2::new(1., 2.));
+        stack.push();
+        stack.translate(V2::new(10., 10.));
+        assert_near(stack.apply(P2::new(0., 0.)), P2::new(11., 12.));
+
+        stack.pop();
+        assert_near(stack.apply(P2::new(0., 0.)), P2::new(1., 2.));
+    }
+
+    #[test]
+    fn nested_operations() {
+        let mut stack = TransformStack::default();
+        stack.translate(V2::new(5., 0.));
+        stack.rotate(Angle::radians(PI / 2.));
+        stack.scale(2.);
+
+        assert_near(stack.apply(P2::new(1., 0.)), P2::new(5., 2.));
+        assert!((stack.rotation().radians - PI / 2.).abs() < 0.0001);
+        assert!((stack.scale_factor() - 2.).abs() < 0.0001);
+    }
+
+    #[test]
+    fn rotation_matches_point_rotation() {
+        let mut stack = TransformStack::default();
+        stack.rotate(Angle::radians(1.));
+
+        let p = P2::new(3., 4.);
+        assert_near(
+            stack.apply(p),
+            p.rotate(P2::new(0., 0.), Angle::radians(1.)),
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Noise warps.
+
+use crate::{NoiseSrc, Polygon, Polyline, P2, P3};
+use noise::{OpenSimplex, Seedable};
+
+/// A trait for types which can be displaced by a noise field for an organic, hand drawn wobble.
+pub trait Warp {
+    /// Displaces each vertex by up to `amplitude` along each axis, following noise with features
+    /// roughly `scale` units across. The same seed always produces the same warp.
+    fn warp(self, amplitude: f32, scale: f32, seed: u32) -> Self;
+}
+
+/// Returns the warp displacement of a point.
+fn warped(noise: &OpenSimplex, p: P2, amplitude: f32, scale: f32) -> P2 {
+    // Sample two distant planes of 3D noise so the axes are displaced independently.
+    let sample = |z| noise.noise(P3::new(p.x / scale, p.y / scale, z));
+    P2::new(p.x + sample(0.) * amplitude, p.y + sample(100.) * amplitude)
+}
+
+impl Warp for P2 {
+    fn warp(self, amplitude: f32, scale: f32, seed: u32) -> Self {
+        warped(&OpenSimplex::new().set_seed(seed), self, amplitude, scale)
+    }
+}
+
+impl Warp for Polygon {
+    fn warp(mut self, amplitude: f32, scale: f32, seed: u32) -> Self {
+        let noise = OpenSimplex::new().set_seed(seed);
+        self.vertices_mut()
+            .for_each(|v| *v = warped(&noise, *v, amplitude, scale));
+        self
+    }
+}
+
+impl Warp for Polyline {
+    fn warp(mut self, amplitude: f32, scale: f32, seed: u32) -> Self {
+        let noise = OpenSimplex::new().set_seed(seed);
+        self.vertices_mut()
+            .for_each(|v| *v = warped(&noise, *v, amplitude, scale));
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Subdivide;
+
+    fn line() -> Polyline {
+        vec![P2::new(0., 0.), P2::new(100., 0.)]
+            .into_iter()
+            .collect::<Polyline>()
+            .subdivide_n(5)
+    }
+
+    fn deviation(line: &Polyline) -> f32 {
+        line.vertices().map(|p| p.y.abs()).sum()
+    }
+
+    #[test]
+    fn amplitude_scales_deviation() {
+        assert_eq!(line().warp(0., 10., 7), line());
+        assert_eq!(line().warp(2., 10., 7), line().warp(2., 10., 7));
+        assert!(deviation(&line().warp(2., 10., 7)) > 0.);
+        assert!(deviation(&line().warp(4., 10., 7)) > deviation(&line().warp(2., 10., 7)));
+    }
+}
@@ -4,7 +4,10 @@ pub use valora_derive::UniformSet;
 
 use glium::{
     texture::{texture2d::Texture2d, texture2d_multisample::Texture2dMultisample},
-    uniforms::{SamplerBehavior, UniformValue},
+    uniforms::{
+        MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior, SamplerWrapFunction,
+        UniformValue,
+    },
 };
 
 /// A trait proxying `glium::Uniforms` for types which own their uniforms.
@@ -145,6 +148,93 @@ referenced_uniform_value!(
     |t| UniformValue::Texture2dMultisample(t, Some(SamplerBehavior::default()))
 );
 
+/// A texture uniform with the wrapping and filtering to sample it with.
+///
+/// Plain `Texture2d` uniforms are sampled with glium's default sampler instead.
+pub struct SampledTexture {
+    pub texture: Texture2d,
+    /// How coordinates outside the texture are mapped into it.
+    pub wrap: TextureWrap,
+    /// How texels are blended when the texture is magnified or minified.
+    pub filter: TextureFilter,
+}
+
+impl SampledTexture {
+    /// Samples the texture with mirrored wrapping and linear filtering.
+    pub fn new(texture: Texture2d) -> Self {
+        Self {
+            texture,
+            wrap: TextureWrap::Mirror,
+            filter: TextureFilter::Linear,
+        }
+    }
+
+    pub fn with_wrap(self, wrap: TextureWrap) -> Self {
+        Self { wrap, ..self }
+    }
+
+    pub fn with_filter(self, filter: TextureFilter) -> Self {
+        Self { filter, ..self }
+    }
+
+    fn sampler(&self) -> SamplerBehavior {
+        let wrap = match self.wrap {
+            TextureWrap::Clamp => SamplerWrapFunction::Clamp,
+            TextureWrap::Repeat => SamplerWrapFunction::Repeat,
+            TextureWrap::Mirror => SamplerWrapFunction::Mirror,
+        };
+        let mipmapped = self.texture.get_mipmap_levels() > 1;
+        let (minify_filter, magnify_filter) = match (self.filter, mipmapped) {
+            (TextureFilter::Nearest, false) => {
+                (MinifySamplerFilter::Nearest, MagnifySamplerFilter::Nearest)
+            }
+            (TextureFilter::Nearest, true) => (
+                MinifySamplerFilter::NearestMipmapNearest,
+                MagnifySamplerFilter::Nearest,
+            ),
+            (TextureFilter::Linear, false) => {
+                (MinifySamplerFilter::Linear, MagnifySamplerFilter::Linear)
+            }
+            (TextureFilter::Linear, true) => (
+                MinifySamplerFilter::LinearMipmapLinear,
+                MagnifySamplerFilter::Linear,
+            ),
+        };
+        SamplerBehavior {
+            wrap_function: (wrap, wrap, wrap),
+            minify_filter,
+            magnify_filter,
+            ..SamplerBehavior::default()
+        }
+    }
+}
+
+/// How texture coordinates outside `[0, 1]` are mapped into a texture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureWrap {
+    /// Coordinates outside the texture take the color of its nearest edge.
+    Clamp,
+    /// The texture tiles.
+    Repeat,
+    /// The texture tiles, flipping every other tile.
+    Mirror,
+}
+
+/// How texels are blended when a texture is drawn larger or smaller than it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureFilter {
+    /// Each sample takes the nearest texel, for hard pixel edges.
+    Nearest,
+    /// Each sample blends the nearest texels, and mipmap levels if the texture has them.
+    Linear,
+}
+
+impl IntoUniformValue for SampledTexture {
+    fn into_uniform_value<'a>(&'a self) -> UniformValue<'a> {
+        UniformValue::Texture2d(&self.texture, Some(self.sampler()))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,331 @@
+//! Encoding saved frames into images, videos and animations.
+
+use crate::Result;
+use failure::format_err;
+use gif::SetParameter;
+use image::RgbaImage;
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+    sync::mpsc::{sync_channel, SyncSender},
+    thread::JoinHandle,
+};
+
+/// An encoder which saved frames are streamed into as they are rendered.
+pub enum FrameEncoder {
+    Images(ImageWriter),
+    Video(Video),
+    Gif(Gif),
+}
+
+impl FrameEncoder {
+    /// Adds a frame. Only image sequences call `path` for where to save it, and fail with its
+    /// error.
+    pub fn write_frame(
+        &mut self,
+        image: RgbaImage,
+        path: impl FnOnce() -> Result<PathBuf>,
+    ) -> Result<()> {
+        match self {
+            FrameEncoder::Images(images) => images.save(image, path()?),
+            FrameEncoder::Video(video) => video.write_frame(&image),
+            FrameEncoder::Gif(gif) => gif.write_frame(&image),
+        }
+    }
+
+    /// Finishes writing the output.
+    pub fn finish(self) -> Result<()> {
+        match self {
+            FrameEncoder::Images(images) => images.finish(),
+            FrameEncoder::Video(video) => video.finish(),
+            FrameEncoder::Gif(gif) => gif.finish(),
+        }
+    }
+}
+
+/// Saves images on a background thread, so encoding and writing them does not hold up rendering.
+///
+/// A few images may wait to be saved at once. Past that, saving blocks until the thread
+/// catches up, so a slow disk slows rendering rather than filling memory.
+pub struct ImageWriter {
+    sender: Option<SyncSender<(RgbaImage, PathBuf)>>,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl ImageWriter {
+    pub fn new() -> Self {
+        /// The most images waiting to be saved at once.
+        const QUEUED_IMAGES: usize = 4;
+
+        let (sender, receiver) = sync_channel::<(RgbaImage, PathBuf)>(QUEUED_IMAGES);
+        let thread = std::thread::spawn(move || {
+            for (image, path) in receiver {
+                image
+                    .save(&path)
+                    .map_err(|e| format_err!("Failed to save {:?}: {}", path, e))?;
+            }
+            Ok(())
+        });
+        Self {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    /// Queues an image to be saved to `path`, in a format following from its extension.
+    pub fn save(&mut self, image: RgbaImage, path: PathBuf) -> Result<()> {
+        let sent = match &self.sender {
+            Some(sender) => sender.send((image, path)).is_ok(),
+            None => false,
+        };
+        if sent {
+            return Ok(());
+        }
+        // The thread only hangs up after failing to save, so report that failure.
+        self.join()?;
+        Err(format_err!("Image writer is already finished"))
+    }
+
+    /// Waits for every queued image to be saved.
+    pub fn finish(mut self) -> Result<()> {
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<()> {
+        self.sender.take();
+        match self.thread.take() {
+            Some(thread) => thread
+                .join()
+                .map_err(|_| format_err!("Image writer thread panicked"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ImageWriter {
+    fn drop(&mut self) {
+        // Save what was queued even if rendering stopped early.
+        let _ = self.join();
+    }
+}
+
+/// A video being encoded by an ffmpeg child process from raw RGBA frames.
+pub struct Video {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl Video {
+    /// Starts encoding a video of frames with the given dimensions to `path`. The container and
+    /// codec follow from the path's extension, as ffmpeg chooses them.
+    ///
+    /// Videos are encoded with chroma subsampling, so both dimensions must be even.
+    pub fn new(path: &Path, width: u32, height: u32, framerate: usize) -> Result<Self> {
+        if !width.is_multiple_of(2) || !height.is_multiple_of(2) {
+            return Err(format_err!(
+                "Video dimensions must be even; got {}x{}",
+                width,
+                height
+            ));
+        }
+        let mut child = Command::new("ffmpeg")
+            .args([
+                "-y",
+                "-loglevel",
+                "error",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgba",
+            ])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-r", &framerate.to_string()])
+            .args(["-i", "-", "-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => format_err!(
+                    "Encoding video requires ffmpeg, which was not found on the PATH: {}",
+                    e
+                ),
+                _ => format_err!("Failed to start ffmpeg: {}", e),
+            })?;
+        let stdin = child.stdin.take();
+        Ok(Self { child, stdin })
+    }
+
+    /// Adds a frame of RGBA bytes to the video.
+    pub fn write_frame(&mut self, rgba: &[u8]) -> Result<()> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| format_err!("Video is already finished"))?;
+        stdin
+            .write_all(rgba)
+            .map_err(|e| format_err!("Failed to write frame to ffmpeg: {}", e))?;
+        Ok(())
+    }
+
+    /// Closes the video and waits for ffmpeg to finish writing it.
+    pub fn finish(mut self) -> Result<()> {
+        let status = self.close()?;
+        if !status.success() {
+            return Err(format_err!("ffmpeg failed to encode video: {}", status));
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<ExitStatus> {
+        // Dropping stdin closes the pipe, which tells ffmpeg the video is over.
+        self.stdin.take();
+        Ok(self.child.wait()?)
+    }
+}
+
+impl Drop for Video {
+    fn drop(&mut self) {
+        // Let ffmpeg finish the file even if rendering stopped early, so it is not truncated.
+        let _ = self.close();
+    }
+}
+
+/// A looping animated GIF.
+pub struct Gif {
+    encoder: gif::Encoder<BufWriter<File>>,
+    width: u16,
+    height: u16,
+    delay: u16,
+}
+
+impl Gif {
+    /// Starts writing a GIF of frames with the given dimensions to `path`, which loops forever
+    /// and shows frames at the given frame rate as closely as GIF's centisecond delays allow.
+    pub fn new(path: &Path, width: u32, height: u32, framerate: usize) -> Result<Self> {
+        let dimension = |d: u32| {
+            u16::try_from(d).map_err(|_| format_err!("GIFs cannot be over 65535 pixels; got {}", d))
+        };
+        let (width, height) = (dimension(width)?, dimension(height)?);
+        let mut encoder =
+            gif::Encoder::new(BufWriter::new(File::create(path)?), width, height, &[])?;
+        encoder.set(gif::Repeat::Infinite)?;
+        Ok(Self {
+            encoder,
+            width,
+            height,
+            delay: (100. / framerate as f32).round().max(1.) as u16,
+        })
+    }
+
+    /// Quantizes a frame of RGBA bytes to a palette and adds it to the GIF.
+    pub fn write_frame(&mut self, rgba: &[u8]) -> Result<()> {
+        /// The NeuQuant sampling factor, trading palette quality for speed; 1 is best, 30 fastest.
+        const QUANTIZATION_SPEED: i32 = 10;
+
+        let mut pixels = rgba.to_vec();
+        let mut frame =
+            gif::Frame::from_rgba_speed(self.width, self.height, &mut pixels, QUANTIZATION_SPEED);
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame)?;
+        Ok(())
+    }
+
+    /// Finishes writing the GIF.
+    pub fn finish(self) -> Result<()> {
+        // The encoder writes the GIF trailer when dropped.
+        drop(self.encoder);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{gif::Decoder, AnimationDecoder};
+
+    #[test]
+    fn image_writer_saves_every_image() {
+        let directory = std::env::temp_dir().join("valora-image-writer");
+        std::fs::create_dir_all(&directory).expect("to create directory");
+        let mut writer = ImageWriter::new();
+        for i in 0..10u8 {
+            let image = RgbaImage::from_pixel(3, 2, image::Rgba([i, 0, 0, 255]));
+            writer
+                .save(image, directory.join(format!("{}.png", i)))
+                .expect("to queue image");
+        }
+        writer.finish().expect("to save images");
+
+        for i in 0..10u8 {
+            let image = image::open(directory.join(format!("{}.png", i)))
+                .expect("to open image")
+                .to_rgba();
+            assert_eq!(image.dimensions(), (3, 2));
+            assert!(image.pixels().all(|p| p[0] == i));
+        }
+        std::fs::remove_dir_all(&directory).expect("to remove directory");
+
+        let mut writer = ImageWriter::new();
+        let missing = directory.join("missing").join("0.png");
+        let image = RgbaImage::new(1, 1);
+        let result = (0..10)
+            .map(|_| writer.save(image.clone(), missing.clone()))
+            .collect::<Result<Vec<_>>>()
+            .and_then(|_| writer.finish());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn frame_path_errors_are_returned() {
+        let mut encoder = FrameEncoder::Images(ImageWriter::new());
+        let result = encoder.write_frame(RgbaImage::new(1, 1), || {
+            Err(format_err!("No room for frames"))
+        });
+        assert_eq!(result.unwrap_err().to_string(), "No room for frames");
+        encoder.finish().expect("to finish with no frames");
+    }
+
+    #[test]
+    fn odd_video_dimensions_are_errors() {
+        let path = std::env::temp_dir().join("valora-odd-video.mp4");
+        for (width, height) in &[(3, 2), (2, 3)] {
+            let error = Video::new(&path, *width, *height, 24)
+                .err()
+                .expect("to reject size");
+            assert!(error.to_string().contains("even"), "{}", error);
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn gif_frames() {
+        let path = std::env::temp_dir().join("valora-gif-frames.gif");
+        let mut gif = Gif::new(&path, 4, 2, 25).expect("to create gif");
+        for shade in &[0u8, 128, 255] {
+            let pixels = [*shade, *shade, *shade, 255].repeat(8);
+            gif.write_frame(&pixels).expect("to write frame");
+        }
+        gif.finish().expect("to finish gif");
+
+        let frames = Decoder::new(File::open(&path).expect("to open gif"))
+            .expect("to decode gif")
+            .into_frames()
+            .collect_frames()
+            .expect("to decode frames");
+        std::fs::remove_file(&path).expect("to remove gif");
+
+        assert_eq!(frames.len(), 3);
+        for (frame, shade) in frames.iter().zip(&[0u8, 128, 255]) {
+            // The decoder reports delays in milliseconds.
+            assert_eq!(frame.delay().to_integer(), 40);
+            // The palette only approximates colors, most of all near black.
+            assert!(frame
+                .buffer()
+                .pixels()
+                .all(|p| (p[0] as i32 - *shade as i32).abs() <= 16));
+        }
+    }
+}